@@ -1,23 +1,223 @@
-use std::{env, path::PathBuf};
+use std::{
+  env,
+  path::{Path, PathBuf},
+};
+
+/// wuffs modules, each compiled in only when the cargo feature of the same name is on.
+const MODULES: &[&str] = &[
+  "adler32", "bmp", "cbor", "crc32", "deflate", "gif", "gzip", "json", "lzw", "nie",
+  "png", "wbmp", "zlib",
+];
+
+/// Modules added by the v0.4 release, their features turn on `v0_4`.
+const MODULES_V0_4: &[&str] = &[
+  "bzip2", "crc64", "jpeg", "lzip", "lzma", "qoi", "sha256", "targa", "xxhash32",
+  "xxhash64", "xz",
+];
+
+/// Directory of the C release inside the `vendor/wuffs` git submodule.
+const VENDOR_DIR: &str = "vendor/wuffs/release/c";
+
+/// wuffs release the crate is built against.
+const VERSION: &str = if cfg!(feature = "v0_4") {
+  "v0.4"
+} else {
+  "v0.3"
+};
 
 fn main() {
-  println!("cargo:rerun-if-changed=vendor/wuffs/release/c/wuffs-v0.3.c");
+  let out = PathBuf::from(env::var("OUT_DIR").unwrap());
+
+  // With `WUFFS_CONFIG__MODULES` defined the amalgamation only compiles the modules that
+  // are explicitly asked for.
+  let mut defines = vec![
+    "WUFFS_CONFIG__MODULES".to_string(),
+    "WUFFS_CONFIG__MODULE__BASE".to_string(),
+  ];
+
+  for module in modules() {
+    let feature = format!("CARGO_FEATURE_{}", module.to_uppercase());
+
+    if env::var_os(feature).is_some() {
+      defines.push(format!("WUFFS_CONFIG__MODULE__{}", module.to_uppercase()));
+    }
+  }
+
+  if cfg!(feature = "v0_4") {
+    defines.push("WUFFS_SYS_V0_4".to_string());
+  }
+
+  if cfg!(all(feature = "native", feature = "portable")) {
+    panic!("The `native` and `portable` features are mutually exclusive");
+  }
+
+  let (cpu_arch, native) = if cfg!(feature = "system") {
+    link_system();
+    (None, false)
+  } else {
+    let (cpu_arch, native) = compile_vendored(&out, &defines);
+    (Some(cpu_arch), native)
+  };
+
+  config(&out, cpu_arch, native);
+  bindings(&out, &defines);
+}
+
+/// Modules available in the selected release.
+fn modules() -> Vec<&'static str> {
+  let mut modules = MODULES.to_vec();
 
+  if cfg!(feature = "v0_4") {
+    modules.extend_from_slice(MODULES_V0_4);
+  }
+
+  modules
+}
+
+/// The single file C amalgamation.
+fn amalgamation() -> String {
+  format!("wuffs-{}.c", VERSION)
+}
+
+/// Compile the amalgamation, returning whether its SIMD code paths were compiled in and
+/// whether the compiler accepted `-march=native`.
+fn compile_vendored(out: &Path, defines: &[String]) -> (bool, bool) {
+  let include = vendored();
   let mut cfg = cc::Build::new();
-  let out = PathBuf::from(env::var("OUT_DIR").unwrap());
+  let arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap();
+  let msvc = env::var("CARGO_CFG_TARGET_ENV").as_deref() == Ok("msvc");
+
+  println!(
+    "cargo:rerun-if-changed={}",
+    include.join(amalgamation()).display()
+  );
 
-  cfg.flag_if_supported("/arch:AVX");
+  // wuffs has SIMD code for x86-64 and, since v0.4, for arm64.  GCC and Clang compile it
+  // with per-function target attributes and pick it at runtime, MSVC only compiles it
+  // when the whole file targets AVX.
+  let mut cpu_arch = arch == "x86_64" || (cfg!(feature = "v0_4") && arch == "aarch64");
+  let mut native = false;
+
+  if cfg!(feature = "portable") {
+    cfg.define("WUFFS_CONFIG__AVOID_CPU_ARCH", None);
+    cpu_arch = false;
+  } else if msvc {
+    let flag = if cfg!(feature = "native") {
+      "/arch:AVX2"
+    } else {
+      "/arch:AVX"
+    };
+
+    let supported = cfg.is_flag_supported(flag).unwrap_or(false);
+    cpu_arch &= supported;
+    native = cfg!(feature = "native") && supported;
+    cfg.flag_if_supported(flag);
+  } else if cfg!(feature = "native") {
+    native = cfg.is_flag_supported("-march=native").unwrap_or(false);
+    cfg.flag_if_supported("-march=native");
+  }
+
+  for define in defines {
+    cfg.define(define, None);
+  }
 
   cfg
     .opt_level(3)
     .define("WUFFS_IMPLEMENTATION", "1")
-    .include("vendor/wuffs/release/c")
-    .file("vendor/wuffs/release/c/wuffs-v0.3.c")
-    .out_dir(out.clone())
+    .include(&include)
+    .file(include.join(amalgamation()))
+    .out_dir(out)
     .compile("libwuffs.a");
 
+  (cpu_arch, native)
+}
+
+/// Record how the C code was built for `wuffs_sys::config`.
+fn config(out: &Path, cpu_arch: Option<bool>, native: bool) {
+  let config = format!(
+    "/// Whether wuffs' SIMD code paths were compiled in, `None` when linking against a\n\
+     /// library built outside of cargo.\n\
+     pub const CPU_ARCH: Option<bool> = {:?};\n\
+     /// Whether the C code was built for the host CPU with `-march=native`.\n\
+     pub const NATIVE: bool = {};\n",
+    cpu_arch, native,
+  );
+
+  std::fs::write(out.join("config.rs"), config).expect("Failed to write config");
+}
+
+/// Link against a wuffs library built outside of cargo, from `WUFFS_LIB_DIR` when set
+/// and through pkg-config otherwise.  The library must be built with the same modules as
+/// the enabled features.
+#[cfg(feature = "system")]
+fn link_system() {
+  println!("cargo:rerun-if-env-changed=WUFFS_LIB_DIR");
+  println!("cargo:rerun-if-env-changed=WUFFS_STATIC");
+
+  match env::var_os("WUFFS_LIB_DIR") {
+    Some(dir) => {
+      let kind = match env::var_os("WUFFS_STATIC") {
+        Some(_) => "static",
+        None => "dylib",
+      };
+
+      println!(
+        "cargo:rustc-link-search=native={}",
+        Path::new(&dir).display()
+      );
+      println!("cargo:rustc-link-lib={}=wuffs", kind);
+    }
+    None => {
+      pkg_config::Config::new()
+        .probe("wuffs")
+        .expect("Failed to find wuffs, set `WUFFS_LIB_DIR` or install a pkg-config file");
+    }
+  }
+}
+
+#[cfg(not(feature = "system"))]
+fn link_system() {
+  unreachable!()
+}
+
+/// Directory holding the vendored amalgamation, which is missing from git checkouts
+/// cloned without `--recursive`.
+fn vendored() -> PathBuf {
+  let dir = PathBuf::from(VENDOR_DIR);
+
+  if !dir.join(amalgamation()).exists() {
+    panic!(
+      "The wuffs C release is missing from `{}`. Run `git submodule update --init` to \
+       fetch it, or enable the `system` feature to link against an installed wuffs.",
+      dir.display()
+    );
+  }
+
+  dir
+}
+
+/// Directory holding the amalgamation for bindgen, `WUFFS_INCLUDE_DIR` for the `system`
+/// feature and the vendored copy otherwise.
+#[cfg(feature = "bindgen")]
+fn include_dir() -> PathBuf {
+  println!("cargo:rerun-if-env-changed=WUFFS_INCLUDE_DIR");
+
+  match env::var_os("WUFFS_INCLUDE_DIR") {
+    Some(dir) if cfg!(feature = "system") => PathBuf::from(dir),
+    _ => vendored(),
+  }
+}
+
+/// Generate bindings with bindgen, which needs libclang.
+///
+/// Setting `WUFFS_SYS_UPDATE_BINDINGS` also writes them to `src/bindings/` so they can be
+/// checked in and used by builds without the `bindgen` feature.
+#[cfg(feature = "bindgen")]
+fn bindings(out: &Path, defines: &[String]) {
   let bindings = bindgen::Builder::default()
     .header("src/lib.h")
+    .clang_arg(format!("-I{}", include_dir().display()))
+    .clang_args(defines.iter().map(|define| format!("-D{}", define)))
     .allowlist_var(".*wuffs.*")
     .allowlist_type(".*wuffs.*")
     .allowlist_function(".*wuffs.*")
@@ -32,4 +232,57 @@ fn main() {
   bindings
     .write_to_file(out.join("bindings.rs"))
     .expect("Failed to write bindings");
+
+  println!("cargo:rerun-if-env-changed=WUFFS_SYS_UPDATE_BINDINGS");
+
+  if env::var_os("WUFFS_SYS_UPDATE_BINDINGS").is_some() {
+    assert!(
+      modules().iter().all(|module| {
+        defines.contains(&format!("WUFFS_CONFIG__MODULE__{}", module.to_uppercase()))
+      }),
+      "Pre-generated bindings must be generated with every module feature enabled"
+    );
+
+    let path = pregenerated();
+    std::fs::create_dir_all(path.parent().unwrap())
+      .expect("Failed to create the bindings directory");
+
+    bindings
+      .write_to_file(path)
+      .expect("Failed to write pre-generated bindings");
+  }
+}
+
+/// Use the checked-in bindings for the target.  These are generated with every module
+/// enabled, the unused declarations of disabled modules are harmless.
+#[cfg(not(feature = "bindgen"))]
+fn bindings(out: &Path, _defines: &[String]) {
+  let path = pregenerated();
+
+  println!("cargo:rerun-if-changed={}", path.display());
+
+  if !path.exists() {
+    panic!(
+      "No pre-generated bindings at `{}`. Keep the default `bindgen` feature to generate \
+       them for this build, or check them in by running `WUFFS_SYS_UPDATE_BINDINGS=1 \
+       cargo build -p wuffs-sys` with every module feature enabled.",
+      path.display()
+    );
+  }
+
+  std::fs::copy(&path, out.join("bindings.rs")).expect("Failed to copy bindings");
+}
+
+/// Path of the checked-in bindings for the target.  The OS and environment are part of
+/// the key along with the architecture, since layouts differ between them, as `long` does
+/// on Windows' LLP64.
+fn pregenerated() -> PathBuf {
+  let target = ["ARCH", "OS", "ENV", "POINTER_WIDTH"]
+    .iter()
+    .map(|key| env::var(format!("CARGO_CFG_TARGET_{}", key)).unwrap_or_default())
+    .filter(|value| !value.is_empty())
+    .collect::<Vec<_>>()
+    .join("-");
+
+  Path::new("src/bindings").join(format!("wuffs-{}-{}.rs", VERSION, target))
 }
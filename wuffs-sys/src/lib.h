@@ -1,2 +1,10 @@
+// Expose the struct definitions, not just the opaque declarations, so bindgen knows the
+// size and alignment of every wuffs type.
+#define WUFFS_IMPLEMENTATION
 #define WUFFS_CONFIG__AVOID_CPU_ARCH
-#include "../vendor/wuffs/release/c/wuffs-v0.3.c";
\ No newline at end of file
+// The include path points at the vendored C release, see `build.rs`.
+#ifdef WUFFS_SYS_V0_4
+#include "wuffs-v0.4.c"
+#else
+#include "wuffs-v0.3.c"
+#endif
@@ -2,3 +2,8 @@
 #![allow(non_upper_case_globals)]
 #![allow(non_camel_case_types)]
 include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+
+/// How the C code was built.
+pub mod config {
+  include!(concat!(env!("OUT_DIR"), "/config.rs"));
+}
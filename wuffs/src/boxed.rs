@@ -1,18 +1,45 @@
-use std::marker::PhantomData;
+use crate::status::WuffsError;
+#[cfg(feature = "alloc")]
+use alloc::{
+  alloc::{alloc_zeroed, dealloc, handle_alloc_error},
+  vec::Vec,
+};
+use core::{
+  alloc::Layout,
+  marker::PhantomData,
+  mem::{align_of, size_of, MaybeUninit},
+  ptr::{self, NonNull},
+};
 
-/// Heap allocated type with runtime defined size.
-#[derive(Clone)]
+/// Memory holding a wuffs type.
+///
+/// # Safety
+/// Implementors must hand out pointers to at least `size()` bytes that are aligned for
+/// `T` and remain valid for as long as the storage is.
+pub unsafe trait WuffsStorage<T> {
+  fn size(&self) -> usize;
+
+  fn as_ptr(&self) -> *const T;
+
+  fn as_mut_ptr(&mut self) -> *mut T;
+}
+
+/// Heap allocated type with runtime defined size.  Only the `alloc` feature can allocate
+/// one, the type exists regardless so that default type parameters don't change with it.
 pub struct WuffsBox<T: WuffsBoxed> {
-  handle: Vec<u8>,
-  phantom: PhantomData<T>,
+  ptr: NonNull<T>,
 }
 
 impl<T: WuffsBoxed> WuffsBox<T> {
-  /// Initialize WuffsBox with supplied size.
+  /// Allocate zeroed memory laid out according to `T::layout()`.
+  #[cfg(feature = "alloc")]
   pub fn new() -> Self {
-    Self {
-      handle: vec![0; T::size()],
-      phantom: Default::default(),
+    let layout = T::layout();
+    let ptr = unsafe { alloc_zeroed(layout) as *mut T };
+
+    match NonNull::new(ptr) {
+      Some(ptr) => Self { ptr },
+      None => handle_alloc_error(layout),
     }
   }
 
@@ -20,31 +47,321 @@ impl<T: WuffsBoxed> WuffsBox<T> {
     T::size()
   }
 
-  /// Cast to pointer
-  ///
-  /// # Safety
-  /// Conversion assumes that underlying allocated buffer is correct size.  Ideally this
-  /// will use the `sizeof__wuffs_*` methods.
-  pub unsafe fn as_ptr(&self) -> *const T {
-    self.handle.as_ptr() as *const T
+  pub fn as_ptr(&self) -> *const T {
+    self.ptr.as_ptr()
+  }
+
+  pub fn as_mut_ptr(&mut self) -> *mut T {
+    self.ptr.as_ptr()
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: WuffsBoxed> Clone for WuffsBox<T> {
+  /// Byte-for-byte copy of the underlying state.
+  fn clone(&self) -> Self {
+    let mut clone = Self::new();
+
+    unsafe {
+      ptr::copy_nonoverlapping(
+        self.as_ptr() as *const u8,
+        clone.as_mut_ptr() as *mut u8,
+        self.size(),
+      );
+    }
+
+    clone
   }
+}
 
-  /// Cast to mutable pointer
-  ///
-  /// # Safety
-  /// Conversion assumes that underlying allocated buffer is correct size.  Ideally this
-  /// will use the `sizeof__wuffs_*` methods.
-  pub unsafe fn as_mut_ptr(&mut self) -> *mut T {
-    self.handle.as_mut_ptr() as *mut T
+#[cfg(feature = "alloc")]
+impl<T: WuffsBoxed> Drop for WuffsBox<T> {
+  fn drop(&mut self) {
+    unsafe { dealloc(self.ptr.as_ptr() as *mut u8, T::layout()) }
   }
 }
 
+#[cfg(feature = "alloc")]
 impl<T: WuffsBoxed> Default for WuffsBox<T> {
   fn default() -> Self {
     Self::new()
   }
 }
 
-pub trait WuffsBoxed {
+// The wuffs state is plain memory without thread affinity, just like the `Vec<u8>` this
+// used to be.
+unsafe impl<T: WuffsBoxed> Send for WuffsBox<T> {}
+unsafe impl<T: WuffsBoxed> Sync for WuffsBox<T> {}
+
+unsafe impl<T: WuffsBoxed> WuffsStorage<T> for WuffsBox<T> {
+  fn size(&self) -> usize {
+    T::size()
+  }
+
+  fn as_ptr(&self) -> *const T {
+    self.ptr.as_ptr()
+  }
+
+  fn as_mut_ptr(&mut self) -> *mut T {
+    self.ptr.as_ptr()
+  }
+}
+
+/// Inline, non-heap storage for types whose layout is known at compile time.
+pub struct WuffsInline<T: WuffsSized> {
+  inner: MaybeUninit<T>,
+}
+
+impl<T: WuffsSized> WuffsInline<T> {
+  pub fn new() -> Self {
+    debug_assert_eq!(size_of::<T>(), T::size());
+
+    Self {
+      inner: MaybeUninit::zeroed(),
+    }
+  }
+
+  pub fn size(&self) -> usize {
+    size_of::<T>()
+  }
+
+  pub fn as_ptr(&self) -> *const T {
+    self.inner.as_ptr()
+  }
+
+  pub fn as_mut_ptr(&mut self) -> *mut T {
+    self.inner.as_mut_ptr()
+  }
+}
+
+impl<T: WuffsSized> Clone for WuffsInline<T> {
+  /// Byte-for-byte copy of the underlying state.
+  fn clone(&self) -> Self {
+    Self {
+      inner: unsafe { ptr::read(&self.inner) },
+    }
+  }
+}
+
+impl<T: WuffsSized> Default for WuffsInline<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+unsafe impl<T: WuffsSized> WuffsStorage<T> for WuffsInline<T> {
+  fn size(&self) -> usize {
+    size_of::<T>()
+  }
+
+  fn as_ptr(&self) -> *const T {
+    self.inner.as_ptr()
+  }
+
+  fn as_mut_ptr(&mut self) -> *mut T {
+    self.inner.as_mut_ptr()
+  }
+}
+
+/// Storage carved out of caller-supplied memory, such as an arena.
+pub struct WuffsBorrowed<'a, T: WuffsBoxed> {
+  ptr: NonNull<T>,
+  _buf: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a, T: WuffsBoxed> WuffsBorrowed<'a, T> {
+  /// Use `buf` as storage.  It must hold at least `T::size()` bytes and start at a
+  /// multiple of `T::layout().align()`.  Its contents don't matter, wuffs clears the
+  /// state when initializing it.
+  pub fn new(buf: &'a mut [u8]) -> Result<Self, WuffsError> {
+    let layout = T::layout();
+    check_len(buf.len(), layout.size())?;
+
+    if buf.as_ptr() as usize & (layout.align() - 1) != 0 {
+      return Err(WuffsError::BufferMisaligned {
+        align: layout.align(),
+      });
+    }
+
+    Ok(Self {
+      ptr: NonNull::from(buf).cast(),
+      _buf: PhantomData,
+    })
+  }
+
+  pub fn size(&self) -> usize {
+    T::size()
+  }
+
+  pub fn as_ptr(&self) -> *const T {
+    self.ptr.as_ptr()
+  }
+
+  pub fn as_mut_ptr(&mut self) -> *mut T {
+    self.ptr.as_ptr()
+  }
+}
+
+// Stands in for the `&mut [u8]` it was made from.
+unsafe impl<T: WuffsBoxed> Send for WuffsBorrowed<'_, T> {}
+unsafe impl<T: WuffsBoxed> Sync for WuffsBorrowed<'_, T> {}
+
+unsafe impl<T: WuffsBoxed> WuffsStorage<T> for WuffsBorrowed<'_, T> {
+  fn size(&self) -> usize {
+    T::size()
+  }
+
+  fn as_ptr(&self) -> *const T {
+    self.ptr.as_ptr()
+  }
+
+  fn as_mut_ptr(&mut self) -> *mut T {
+    self.ptr.as_ptr()
+  }
+}
+
+/// Fail unless a caller-supplied buffer of `len` bytes holds at least `needed` bytes.
+pub(crate) fn check_len(len: usize, needed: usize) -> Result<(), WuffsError> {
+  if len < needed {
+    return Err(WuffsError::BufferTooSmall { needed, len });
+  }
+
+  Ok(())
+}
+
+/// Work buffer of a decoder whose needs depend on the image being decoded.
+pub trait WuffsWork {
+  /// Borrow at least `len` bytes, growing the buffer if it can.
+  fn reserve(&mut self, len: usize) -> Result<&mut [u8], WuffsError>;
+}
+
+#[cfg(feature = "alloc")]
+impl WuffsWork for Vec<u8> {
+  fn reserve(&mut self, len: usize) -> Result<&mut [u8], WuffsError> {
+    if self.len() < len {
+      self.resize(len, 0);
+    }
+
+    Ok(self)
+  }
+}
+
+impl WuffsWork for &mut [u8] {
+  fn reserve(&mut self, len: usize) -> Result<&mut [u8], WuffsError> {
+    check_len(self.len(), len)?;
+
+    Ok(self)
+  }
+}
+
+/// Heap allocated work buffer that grows to what the decoder asks for, picked when none
+/// is given explicitly.  Like [`WuffsBox`], only the `alloc` feature can create one.
+#[derive(Clone)]
+#[cfg_attr(feature = "alloc", derive(Default))]
+pub struct WuffsHeapWork {
+  #[cfg(feature = "alloc")]
+  buf: Vec<u8>,
+}
+
+#[cfg(feature = "alloc")]
+impl WuffsWork for WuffsHeapWork {
+  fn reserve(&mut self, len: usize) -> Result<&mut [u8], WuffsError> {
+    WuffsWork::reserve(&mut self.buf, len)
+  }
+}
+
+/// Storage picked when none is given explicitly.  Without the `alloc` feature decoders
+/// and hashers have no `new`, pass [`WuffsInline`] or [`WuffsBorrowed`] storage to their
+/// `with_storage` instead.
+pub type WuffsDefault<T> = WuffsBox<T>;
+
+pub trait WuffsBoxed: Sized {
+  /// Size reported by the `sizeof__wuffs_*` functions.
   fn size() -> usize;
+
+  /// Layout used to allocate the type.  Alignment comes from the bindgen type but never
+  /// drops below `u64`, so a type bindgen only sees as opaque is still safe to use.
+  fn layout() -> Layout {
+    let align = align_of::<Self>().max(align_of::<u64>());
+
+    Layout::from_size_align(Self::size().max(1), align)
+      .expect("invalid wuffs type layout")
+  }
+}
+
+/// Type whose bindgen definition is complete, so it can be stored inline.
+///
+/// # Safety
+/// `size_of::<Self>()` must equal `Self::size()` and an all-zero bit pattern must be
+/// valid for the type.
+pub unsafe trait WuffsSized: WuffsBoxed {}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  #[cfg(all(feature = "alloc", feature = "gzip"))]
+  fn test_box_layout() {
+    use super::{WuffsBox, WuffsBoxed};
+    use std::mem::{align_of, size_of};
+    use wuffs_sys::wuffs_gzip__decoder;
+
+    let boxed = WuffsBox::<wuffs_gzip__decoder>::new();
+
+    assert_eq!(
+      boxed.as_ptr() as usize % align_of::<wuffs_gzip__decoder>(),
+      0
+    );
+    assert_eq!(
+      wuffs_gzip__decoder::size(),
+      size_of::<wuffs_gzip__decoder>()
+    );
+  }
+
+  #[test]
+  #[cfg(feature = "crc32")]
+  fn test_inline_size() {
+    use super::{WuffsBoxed, WuffsInline, WuffsStorage};
+    use wuffs_sys::wuffs_crc32__ieee_hasher;
+
+    let inline = WuffsInline::<wuffs_crc32__ieee_hasher>::new();
+
+    assert_eq!(
+      WuffsStorage::size(&inline),
+      wuffs_crc32__ieee_hasher::size()
+    );
+  }
+
+  #[test]
+  #[cfg(feature = "crc32")]
+  fn test_borrowed() {
+    use super::{WuffsBorrowed, WuffsBoxed, WuffsStorage};
+    use crate::status::WuffsError;
+    use wuffs_sys::wuffs_crc32__ieee_hasher;
+
+    let layout = wuffs_crc32__ieee_hasher::layout();
+    let mut words = [0u64; 64];
+    let buf =
+      unsafe { std::slice::from_raw_parts_mut(words.as_mut_ptr() as *mut u8, 64 * 8) };
+
+    assert_eq!(
+      WuffsBorrowed::<wuffs_crc32__ieee_hasher>::new(&mut buf[..layout.size() - 1]).err(),
+      Some(WuffsError::BufferTooSmall {
+        needed: layout.size(),
+        len: layout.size() - 1,
+      })
+    );
+    assert_eq!(
+      WuffsBorrowed::<wuffs_crc32__ieee_hasher>::new(&mut buf[1..]).err(),
+      Some(WuffsError::BufferMisaligned {
+        align: layout.align(),
+      })
+    );
+
+    let borrowed = WuffsBorrowed::<wuffs_crc32__ieee_hasher>::new(buf).unwrap();
+
+    assert_eq!(
+      WuffsStorage::size(&borrowed),
+      wuffs_crc32__ieee_hasher::size()
+    );
+  }
 }
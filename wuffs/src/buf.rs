@@ -1,7 +1,8 @@
 use crate::slice::WuffsSlice;
+use core::slice;
 use wuffs_sys::{wuffs_base__io_buffer, wuffs_base__io_buffer_meta};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug)]
 pub struct WuffsBuf(wuffs_base__io_buffer);
 
 impl WuffsBuf {
@@ -54,4 +55,33 @@ impl WuffsBuf {
   pub fn written(&self) -> usize {
     self.0.meta.wi as _
   }
+
+  /// Position in the whole stream of the first byte of the buffer, for a buffer that
+  /// holds a window onto a longer stream.
+  pub fn set_pos(&mut self, pos: u64) {
+    self.0.meta.pos = pos;
+  }
+
+  /// Whether no more bytes will be written.  Decoders report a short read on an open
+  /// buffer that runs out, and an error on a closed one.
+  pub fn set_closed(&mut self, closed: bool) {
+    self.0.meta.closed = closed;
+  }
+
+  /// Bytes written to the buffer that haven't been read yet.
+  ///
+  /// # Safety
+  /// The slice the buffer was made from must still be alive, the buffer doesn't borrow
+  /// it.
+  pub unsafe fn unread(&self) -> &[u8] {
+    let meta = &self.0.meta;
+
+    slice::from_raw_parts(self.0.data.ptr.add(meta.ri as _), (meta.wi - meta.ri) as _)
+  }
+
+  /// Mark up to `n` more bytes as read.
+  pub fn skip(&mut self, n: usize) {
+    let meta = &mut self.0.meta;
+    meta.ri = (meta.ri as usize + n).min(meta.wi as usize) as _;
+  }
 }
@@ -0,0 +1,170 @@
+use wuffs_sys::config;
+
+/// An accelerated code path in the C code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuFeature {
+  /// Compiled into the C code.  `None` when linked against a wuffs library built outside
+  /// of cargo, for which this can't be known.
+  pub compiled: Option<bool>,
+  /// Supported by the CPU the program is running on.  Without the `std` feature there is
+  /// no runtime detection and this only reports what the compile target guarantees.
+  pub detected: bool,
+}
+
+impl CpuFeature {
+  fn new(compiled: Option<bool>, detected: bool) -> Self {
+    Self { compiled, detected }
+  }
+
+  /// Whether wuffs takes this code path, assuming a system library has it compiled in.
+  pub fn is_active(&self) -> bool {
+    self.compiled.unwrap_or(true) && self.detected
+  }
+}
+
+/// Accelerated code paths of the C code, see [`cpu_features`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuFeatures {
+  /// Built for the host CPU with the `native` feature.
+  pub native: bool,
+  /// SSE4.2, PCLMUL and POPCNT on x86-64, used by CRC-32, Adler-32, PNG filters and the
+  /// pixel swizzler.
+  pub x86_sse42: CpuFeature,
+  /// AVX2 on x86-64, used by wuffs v0.4.
+  pub x86_avx2: CpuFeature,
+  /// CRC32 instructions on arm64, used by wuffs v0.4.
+  pub arm_crc32: CpuFeature,
+  /// NEON on arm64, used by wuffs v0.4.
+  pub arm_neon: CpuFeature,
+}
+
+/// Report which accelerated code paths were compiled in and which are supported by the
+/// running CPU.  wuffs picks between them at runtime, except with the `portable` feature
+/// where only the scalar code is compiled.
+pub fn cpu_features() -> CpuFeatures {
+  let x86_64 = cfg!(target_arch = "x86_64");
+  let aarch64 = cfg!(target_arch = "aarch64");
+  let v0_4 = cfg!(feature = "v0_4");
+
+  let compiled = |arch: bool| config::CPU_ARCH.map(|compiled| compiled && arch);
+
+  CpuFeatures {
+    native: config::NATIVE,
+    x86_sse42: CpuFeature::new(compiled(x86_64), detect::x86_sse42()),
+    x86_avx2: CpuFeature::new(compiled(x86_64 && v0_4), detect::x86_avx2()),
+    arm_crc32: CpuFeature::new(compiled(aarch64 && v0_4), detect::arm_crc32()),
+    arm_neon: CpuFeature::new(compiled(aarch64 && v0_4), detect::arm_neon()),
+  }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod detect {
+  pub fn x86_sse42() -> bool {
+    #[cfg(feature = "std")]
+    {
+      is_x86_feature_detected!("sse4.2")
+        && is_x86_feature_detected!("pclmulqdq")
+        && is_x86_feature_detected!("popcnt")
+    }
+
+    #[cfg(not(feature = "std"))]
+    {
+      cfg!(all(
+        target_feature = "sse4.2",
+        target_feature = "pclmulqdq",
+        target_feature = "popcnt"
+      ))
+    }
+  }
+
+  pub fn x86_avx2() -> bool {
+    #[cfg(feature = "std")]
+    {
+      x86_sse42() && is_x86_feature_detected!("avx2")
+    }
+
+    #[cfg(not(feature = "std"))]
+    {
+      x86_sse42() && cfg!(target_feature = "avx2")
+    }
+  }
+
+  pub fn arm_crc32() -> bool {
+    false
+  }
+
+  pub fn arm_neon() -> bool {
+    false
+  }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod detect {
+  pub fn x86_sse42() -> bool {
+    false
+  }
+
+  pub fn x86_avx2() -> bool {
+    false
+  }
+
+  pub fn arm_crc32() -> bool {
+    #[cfg(feature = "std")]
+    {
+      std::arch::is_aarch64_feature_detected!("crc")
+    }
+
+    #[cfg(not(feature = "std"))]
+    {
+      cfg!(target_feature = "crc")
+    }
+  }
+
+  pub fn arm_neon() -> bool {
+    #[cfg(feature = "std")]
+    {
+      std::arch::is_aarch64_feature_detected!("neon")
+    }
+
+    #[cfg(not(feature = "std"))]
+    {
+      cfg!(target_feature = "neon")
+    }
+  }
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+mod detect {
+  pub fn x86_sse42() -> bool {
+    false
+  }
+
+  pub fn x86_avx2() -> bool {
+    false
+  }
+
+  pub fn arm_crc32() -> bool {
+    false
+  }
+
+  pub fn arm_neon() -> bool {
+    false
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  fn test_cpu_features() {
+    let features = super::cpu_features();
+
+    if cfg!(feature = "portable") {
+      assert_eq!(features.x86_sse42.compiled, Some(false));
+      assert!(!features.x86_sse42.is_active());
+    }
+
+    if !cfg!(target_arch = "x86_64") {
+      assert!(!features.x86_sse42.detected);
+    }
+  }
+}
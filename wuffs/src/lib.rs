@@ -1,5 +1,16 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 pub mod boxed;
 pub mod buf;
+pub mod cpu;
+pub mod pixel;
 pub mod slice;
 pub mod status;
 pub mod std;
+pub mod swizzler;
+
+pub use crate::std::image;
+pub use cpu::cpu_features;
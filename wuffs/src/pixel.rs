@@ -0,0 +1,487 @@
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+use core::{marker::PhantomData, mem, slice};
+
+use crate::{boxed::check_len, status::WuffsError};
+use wuffs_sys::{
+  wuffs_base__error__bad_argument, wuffs_base__error__unsupported_option,
+  wuffs_base__pixel_buffer, wuffs_base__pixel_config, wuffs_base__table_u8,
+  WUFFS_BASE__PIXEL_FORMAT__BGR, WUFFS_BASE__PIXEL_FORMAT__BGRA_BINARY,
+  WUFFS_BASE__PIXEL_FORMAT__BGRA_NONPREMUL, WUFFS_BASE__PIXEL_FORMAT__BGRA_PREMUL,
+  WUFFS_BASE__PIXEL_FORMAT__BGRX, WUFFS_BASE__PIXEL_FORMAT__INDEXED__BGRA_BINARY,
+  WUFFS_BASE__PIXEL_FORMAT__INDEXED__BGRA_NONPREMUL,
+  WUFFS_BASE__PIXEL_FORMAT__INDEXED__BGRA_PREMUL,
+  WUFFS_BASE__PIXEL_FORMAT__INDEXED__COLOR_PLANE, WUFFS_BASE__PIXEL_FORMAT__RGB,
+  WUFFS_BASE__PIXEL_FORMAT__RGBA_NONPREMUL, WUFFS_BASE__PIXEL_FORMAT__RGBA_PREMUL,
+  WUFFS_BASE__PIXEL_FORMAT__Y, WUFFS_BASE__PIXEL_SUBSAMPLING__NONE,
+};
+
+/// Bytes in the BGRA palette of an indexed pixel format.
+pub const PALETTE_LEN: usize = 1024;
+
+/// Interleaved pixel formats that wuffs can decode into.  Channels are listed in memory
+/// order, so `Bgra*` stores blue in the first byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WuffsPixelFormat {
+  /// 8-bit gray.
+  Y,
+  Bgr,
+  Rgb,
+  /// BGRA with the alpha byte ignored.
+  Bgrx,
+  BgraNonpremul,
+  BgraPremul,
+  /// BGRA whose alpha is either fully opaque or fully transparent.
+  BgraBinary,
+  RgbaNonpremul,
+  RgbaPremul,
+  /// One byte per pixel indexing into a [`PALETTE_LEN`] byte BGRA palette.
+  IndexedBgraNonpremul,
+  IndexedBgraPremul,
+  IndexedBgraBinary,
+}
+
+impl WuffsPixelFormat {
+  /// Map a `wuffs_base__pixel_format` value to its variant, `None` for formats without
+  /// one, such as planar or 16-bit formats.
+  pub fn from_repr(repr: u32) -> Option<Self> {
+    let format = match repr {
+      WUFFS_BASE__PIXEL_FORMAT__Y => Self::Y,
+      WUFFS_BASE__PIXEL_FORMAT__BGR => Self::Bgr,
+      WUFFS_BASE__PIXEL_FORMAT__RGB => Self::Rgb,
+      WUFFS_BASE__PIXEL_FORMAT__BGRX => Self::Bgrx,
+      WUFFS_BASE__PIXEL_FORMAT__BGRA_NONPREMUL => Self::BgraNonpremul,
+      WUFFS_BASE__PIXEL_FORMAT__BGRA_PREMUL => Self::BgraPremul,
+      WUFFS_BASE__PIXEL_FORMAT__BGRA_BINARY => Self::BgraBinary,
+      WUFFS_BASE__PIXEL_FORMAT__RGBA_NONPREMUL => Self::RgbaNonpremul,
+      WUFFS_BASE__PIXEL_FORMAT__RGBA_PREMUL => Self::RgbaPremul,
+      WUFFS_BASE__PIXEL_FORMAT__INDEXED__BGRA_NONPREMUL => Self::IndexedBgraNonpremul,
+      WUFFS_BASE__PIXEL_FORMAT__INDEXED__BGRA_PREMUL => Self::IndexedBgraPremul,
+      WUFFS_BASE__PIXEL_FORMAT__INDEXED__BGRA_BINARY => Self::IndexedBgraBinary,
+      _ => return None,
+    };
+
+    Some(format)
+  }
+
+  pub fn repr(self) -> u32 {
+    match self {
+      Self::Y => WUFFS_BASE__PIXEL_FORMAT__Y,
+      Self::Bgr => WUFFS_BASE__PIXEL_FORMAT__BGR,
+      Self::Rgb => WUFFS_BASE__PIXEL_FORMAT__RGB,
+      Self::Bgrx => WUFFS_BASE__PIXEL_FORMAT__BGRX,
+      Self::BgraNonpremul => WUFFS_BASE__PIXEL_FORMAT__BGRA_NONPREMUL,
+      Self::BgraPremul => WUFFS_BASE__PIXEL_FORMAT__BGRA_PREMUL,
+      Self::BgraBinary => WUFFS_BASE__PIXEL_FORMAT__BGRA_BINARY,
+      Self::RgbaNonpremul => WUFFS_BASE__PIXEL_FORMAT__RGBA_NONPREMUL,
+      Self::RgbaPremul => WUFFS_BASE__PIXEL_FORMAT__RGBA_PREMUL,
+      Self::IndexedBgraNonpremul => WUFFS_BASE__PIXEL_FORMAT__INDEXED__BGRA_NONPREMUL,
+      Self::IndexedBgraPremul => WUFFS_BASE__PIXEL_FORMAT__INDEXED__BGRA_PREMUL,
+      Self::IndexedBgraBinary => WUFFS_BASE__PIXEL_FORMAT__INDEXED__BGRA_BINARY,
+    }
+  }
+
+  pub fn bytes_per_pixel(self) -> usize {
+    match self {
+      Self::Y
+      | Self::IndexedBgraNonpremul
+      | Self::IndexedBgraPremul
+      | Self::IndexedBgraBinary => 1,
+      Self::Bgr | Self::Rgb => 3,
+      Self::Bgrx
+      | Self::BgraNonpremul
+      | Self::BgraPremul
+      | Self::BgraBinary
+      | Self::RgbaNonpremul
+      | Self::RgbaPremul => 4,
+    }
+  }
+
+  pub fn is_indexed(self) -> bool {
+    matches!(
+      self,
+      Self::IndexedBgraNonpremul | Self::IndexedBgraPremul | Self::IndexedBgraBinary
+    )
+  }
+}
+
+/// Pixel format and dimensions of an image.
+#[derive(Debug, Clone, Copy)]
+pub struct WuffsPixelConfig(wuffs_base__pixel_config);
+
+impl WuffsPixelConfig {
+  pub fn new(format: WuffsPixelFormat, width: u32, height: u32) -> Self {
+    let mut inner: wuffs_base__pixel_config = unsafe { mem::zeroed() };
+    inner.private_impl.pixfmt.repr = format.repr();
+    inner.private_impl.pixsub.repr = WUFFS_BASE__PIXEL_SUBSAMPLING__NONE;
+    inner.private_impl.width = width;
+    inner.private_impl.height = height;
+
+    Self(inner)
+  }
+
+  pub fn from_inner(inner: wuffs_base__pixel_config) -> Self {
+    Self(inner)
+  }
+
+  pub fn into_inner(self) -> wuffs_base__pixel_config {
+    self.0
+  }
+
+  pub fn as_mut_ptr(&mut self) -> *mut wuffs_base__pixel_config {
+    &mut self.0 as *mut _
+  }
+
+  /// `None` when wuffs picked a format without a [`WuffsPixelFormat`] variant.
+  pub fn format(&self) -> Option<WuffsPixelFormat> {
+    WuffsPixelFormat::from_repr(self.0.private_impl.pixfmt.repr)
+  }
+
+  pub fn width(&self) -> u32 {
+    self.0.private_impl.width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.0.private_impl.height
+  }
+
+  /// Bytes needed to hold the image without padding, palette included.  `None` for an
+  /// unsupported format or when the size overflows.
+  pub fn pixbuf_len(&self) -> Option<usize> {
+    let layout = Layout::new(self, None)?;
+    layout.palette_len.checked_add(layout.plane_len)
+  }
+}
+
+/// Where the planes of a pixel buffer sit in its memory.
+struct Layout {
+  palette_len: usize,
+  row_len: usize,
+  stride: usize,
+  plane_len: usize,
+}
+
+impl Layout {
+  fn new(config: &WuffsPixelConfig, stride: Option<usize>) -> Option<Self> {
+    let format = config.format()?;
+    let row_len = (config.width() as usize).checked_mul(format.bytes_per_pixel())?;
+    let stride = stride.unwrap_or(row_len);
+
+    // The last row doesn't need padding up to the stride.
+    let plane_len = match config.height() as usize {
+      0 => 0,
+      height => (height - 1).checked_mul(stride)?.checked_add(row_len)?,
+    };
+
+    Some(Self {
+      palette_len: if format.is_indexed() { PALETTE_LEN } else { 0 },
+      row_len,
+      stride,
+      plane_len,
+    })
+  }
+}
+
+/// Decoded pixels with a [`WuffsPixelConfig`], in memory that is either owned or
+/// borrowed from the caller.  Indexed formats keep their palette in the first
+/// [`PALETTE_LEN`] bytes, followed by the rows.
+pub struct WuffsPixelBuffer<'a> {
+  inner: wuffs_base__pixel_buffer,
+  #[cfg(feature = "alloc")]
+  _owned: Option<Vec<u8>>,
+  _buf: PhantomData<&'a mut [u8]>,
+}
+
+#[cfg(feature = "alloc")]
+impl WuffsPixelBuffer<'static> {
+  /// Allocate a zeroed buffer without padding between rows.
+  pub fn new(config: WuffsPixelConfig) -> Result<Self, WuffsError> {
+    let len = config.pixbuf_len().ok_or_else(unsupported)?;
+    let mut owned = vec![0; len];
+    let inner = unsafe { Self::init(config, owned.as_mut_ptr(), owned.len(), None)? };
+
+    Ok(Self {
+      inner,
+      _owned: Some(owned),
+      _buf: PhantomData,
+    })
+  }
+}
+
+impl<'a> WuffsPixelBuffer<'a> {
+  /// Use `buf` for the pixels, without padding between rows.
+  pub fn from_slice(
+    config: WuffsPixelConfig,
+    buf: &'a mut [u8],
+  ) -> Result<Self, WuffsError> {
+    Self::borrowed(config, buf, None)
+  }
+
+  /// Use `buf` for the pixels, with rows starting `stride` bytes apart.
+  pub fn from_slice_with_stride(
+    config: WuffsPixelConfig,
+    buf: &'a mut [u8],
+    stride: usize,
+  ) -> Result<Self, WuffsError> {
+    Self::borrowed(config, buf, Some(stride))
+  }
+
+  fn borrowed(
+    config: WuffsPixelConfig,
+    buf: &'a mut [u8],
+    stride: Option<usize>,
+  ) -> Result<Self, WuffsError> {
+    let inner = unsafe { Self::init(config, buf.as_mut_ptr(), buf.len(), stride)? };
+
+    Ok(Self {
+      inner,
+      #[cfg(feature = "alloc")]
+      _owned: None,
+      _buf: PhantomData,
+    })
+  }
+
+  /// # Safety
+  /// `ptr` must be valid for reads and writes of `len` bytes for as long as the returned
+  /// pixel buffer is used.
+  unsafe fn init(
+    config: WuffsPixelConfig,
+    ptr: *mut u8,
+    len: usize,
+    stride: Option<usize>,
+  ) -> Result<wuffs_base__pixel_buffer, WuffsError> {
+    let layout = Layout::new(&config, stride).ok_or_else(unsupported)?;
+
+    if layout.stride < layout.row_len {
+      return Err(bad_argument());
+    }
+
+    let needed = layout
+      .palette_len
+      .checked_add(layout.plane_len)
+      .ok_or_else(unsupported)?;
+    check_len(len, needed)?;
+
+    let mut inner: wuffs_base__pixel_buffer = mem::zeroed();
+    inner.pixcfg = config.into_inner();
+
+    if layout.palette_len > 0 {
+      inner.private_impl.planes
+        [WUFFS_BASE__PIXEL_FORMAT__INDEXED__COLOR_PLANE as usize] =
+        wuffs_base__table_u8 {
+          ptr,
+          width: PALETTE_LEN as _,
+          height: 1,
+          stride: PALETTE_LEN as _,
+        };
+    }
+
+    inner.private_impl.planes[0] = wuffs_base__table_u8 {
+      ptr: ptr.add(layout.palette_len),
+      width: layout.row_len as _,
+      height: config.height() as _,
+      stride: layout.stride as _,
+    };
+
+    Ok(inner)
+  }
+
+  pub fn as_ptr(&self) -> *const wuffs_base__pixel_buffer {
+    &self.inner as *const _
+  }
+
+  pub fn as_mut_ptr(&mut self) -> *mut wuffs_base__pixel_buffer {
+    &mut self.inner as *mut _
+  }
+
+  pub fn config(&self) -> WuffsPixelConfig {
+    WuffsPixelConfig::from_inner(self.inner.pixcfg)
+  }
+
+  pub fn format(&self) -> WuffsPixelFormat {
+    self
+      .config()
+      .format()
+      .expect("pixel buffers are only built for supported formats")
+  }
+
+  pub fn width(&self) -> u32 {
+    self.config().width()
+  }
+
+  pub fn height(&self) -> u32 {
+    self.config().height()
+  }
+
+  /// Bytes from the start of one row to the start of the next.
+  pub fn stride(&self) -> usize {
+    self.plane().stride as _
+  }
+
+  fn plane(&self) -> &wuffs_base__table_u8 {
+    &self.inner.private_impl.planes[0]
+  }
+
+  /// Pointer to row `y`, which must be less than the height, and the row's length.
+  fn row_ptr(&self, y: usize) -> (*mut u8, usize) {
+    let plane = self.plane();
+
+    unsafe {
+      (
+        plane.ptr.add(y * plane.stride as usize),
+        plane.width as usize,
+      )
+    }
+  }
+
+  /// Pixels of row `y`, without padding.
+  pub fn row(&self, y: u32) -> Option<&[u8]> {
+    if y >= self.height() {
+      return None;
+    }
+
+    let (ptr, len) = self.row_ptr(y as _);
+    Some(unsafe { slice::from_raw_parts(ptr, len) })
+  }
+
+  pub fn row_mut(&mut self, y: u32) -> Option<&mut [u8]> {
+    if y >= self.height() {
+      return None;
+    }
+
+    let (ptr, len) = self.row_ptr(y as _);
+    Some(unsafe { slice::from_raw_parts_mut(ptr, len) })
+  }
+
+  pub fn rows(&self) -> impl Iterator<Item = &[u8]> + '_ {
+    (0..self.height()).map(move |y| {
+      let (ptr, len) = self.row_ptr(y as _);
+      unsafe { slice::from_raw_parts(ptr, len) }
+    })
+  }
+
+  pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [u8]> + '_ {
+    let this = &*self;
+
+    // Rows never overlap since the stride is at least the row length.
+    (0..this.height()).map(move |y| {
+      let (ptr, len) = this.row_ptr(y as _);
+      unsafe { slice::from_raw_parts_mut(ptr, len) }
+    })
+  }
+
+  /// Bytes of the pixel at `x`, `y`.
+  pub fn pixel(&self, x: u32, y: u32) -> Option<&[u8]> {
+    let bytes = self.format().bytes_per_pixel();
+    let start = (x as usize).checked_mul(bytes)?;
+
+    self.row(y)?.get(start..start + bytes)
+  }
+
+  pub fn pixel_mut(&mut self, x: u32, y: u32) -> Option<&mut [u8]> {
+    let bytes = self.format().bytes_per_pixel();
+    let start = (x as usize).checked_mul(bytes)?;
+
+    self.row_mut(y)?.get_mut(start..start + bytes)
+  }
+
+  /// BGRA palette of an indexed format.
+  pub fn palette(&self) -> Option<&[u8]> {
+    let table = self.palette_table()?;
+
+    Some(unsafe { slice::from_raw_parts(table.ptr, PALETTE_LEN) })
+  }
+
+  pub fn palette_mut(&mut self) -> Option<&mut [u8]> {
+    let table = self.palette_table()?;
+
+    Some(unsafe { slice::from_raw_parts_mut(table.ptr, PALETTE_LEN) })
+  }
+
+  fn palette_table(&self) -> Option<&wuffs_base__table_u8> {
+    let table = &self.inner.private_impl.planes
+      [WUFFS_BASE__PIXEL_FORMAT__INDEXED__COLOR_PLANE as usize];
+
+    if table.ptr.is_null() {
+      None
+    } else {
+      Some(table)
+    }
+  }
+}
+
+// Stands in for the `&mut [u8]` or `Vec<u8>` the planes point into.
+unsafe impl Send for WuffsPixelBuffer<'_> {}
+unsafe impl Sync for WuffsPixelBuffer<'_> {}
+
+fn bad_argument() -> WuffsError {
+  unsafe { WuffsError::from_ptr(wuffs_base__error__bad_argument.as_ptr()) }
+}
+
+fn unsupported() -> WuffsError {
+  unsafe { WuffsError::from_ptr(wuffs_base__error__unsupported_option.as_ptr()) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{WuffsPixelBuffer, WuffsPixelConfig, WuffsPixelFormat, PALETTE_LEN};
+  use crate::status::WuffsError;
+
+  #[test]
+  fn test_pixel_format_repr() {
+    let format = WuffsPixelFormat::IndexedBgraBinary;
+
+    assert_eq!(WuffsPixelFormat::from_repr(format.repr()), Some(format));
+    assert_eq!(WuffsPixelFormat::from_repr(0), None);
+  }
+
+  #[test]
+  fn test_pixel_buffer_stride() {
+    let config = WuffsPixelConfig::new(WuffsPixelFormat::Rgb, 2, 3);
+    let mut buf = [0; 2 * 8 + 6];
+
+    {
+      let mut pixbuf =
+        WuffsPixelBuffer::from_slice_with_stride(config, &mut buf, 8).unwrap();
+
+      pixbuf.pixel_mut(1, 2).unwrap().copy_from_slice(&[1, 2, 3]);
+
+      assert_eq!(pixbuf.rows().count(), 3);
+      assert_eq!(pixbuf.row(2).unwrap(), &[0, 0, 0, 1, 2, 3]);
+      assert_eq!(pixbuf.pixel(2, 2), None);
+      assert_eq!(pixbuf.row(3), None);
+    }
+
+    assert_eq!(&buf[16..], &[0, 0, 0, 1, 2, 3]);
+  }
+
+  #[test]
+  fn test_pixel_buffer_indexed() {
+    let config = WuffsPixelConfig::new(WuffsPixelFormat::IndexedBgraBinary, 4, 4);
+    let mut buf = [0; PALETTE_LEN + 16];
+
+    {
+      let mut pixbuf = WuffsPixelBuffer::from_slice(config, &mut buf).unwrap();
+
+      pixbuf.palette_mut().unwrap()[..4].copy_from_slice(&[1, 2, 3, 4]);
+      pixbuf.row_mut(0).unwrap()[0] = 5;
+    }
+
+    assert_eq!(&buf[..4], &[1, 2, 3, 4]);
+    assert_eq!(buf[PALETTE_LEN], 5);
+  }
+
+  #[test]
+  fn test_pixel_buffer_too_small() {
+    let config = WuffsPixelConfig::new(WuffsPixelFormat::BgraPremul, 4, 4);
+    let mut buf = [0; 63];
+
+    assert_eq!(
+      WuffsPixelBuffer::from_slice(config, &mut buf).err(),
+      Some(WuffsError::BufferTooSmall {
+        needed: 64,
+        len: 63
+      })
+    );
+  }
+}
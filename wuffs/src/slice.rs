@@ -1,9 +1,12 @@
-use std::{marker::PhantomData, ops::Deref};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::{marker::PhantomData, ops::Deref};
 
 use wuffs_sys::wuffs_base__slice_u8;
 
-#[derive(Clone)]
+/// Not `Clone`: a borrowed slice wraps a `&mut [T]` and cloning it would alias it.
 pub enum WuffsSlice<'a, T: WuffsSliceImpl> {
+  #[cfg(feature = "alloc")]
   Owned(WuffsSliceOwned<T>),
   Borrowed(WuffsSliceBorrowed<'a, T>),
 }
@@ -15,6 +18,7 @@ impl<T: WuffsSliceImpl> WuffsSlice<'_, T> {
 
   pub fn into_inner(self) -> T::Native {
     match self {
+      #[cfg(feature = "alloc")]
       Self::Owned(owned) => owned.into_inner(),
       Self::Borrowed(borrowed) => borrowed.into_inner(),
     }
@@ -42,6 +46,7 @@ impl<'a, T: WuffsSliceImpl> From<&'a mut [T]> for WuffsSlice<'a, T> {
   }
 }
 
+#[cfg(feature = "alloc")]
 impl<'a, T: WuffsSliceImpl> From<Vec<T>> for WuffsSlice<'a, T> {
   fn from(vec: Vec<T>) -> Self {
     Self::Owned(WuffsSliceOwned::new(vec))
@@ -53,18 +58,20 @@ impl<T: WuffsSliceImpl> Deref for WuffsSlice<'_, T> {
 
   fn deref(&self) -> &Self::Target {
     match self {
+      #[cfg(feature = "alloc")]
       Self::Owned(owned) => owned,
       Self::Borrowed(borrowed) => borrowed,
     }
   }
 }
 
-#[derive(Clone)]
+#[cfg(feature = "alloc")]
 pub struct WuffsSliceOwned<T: WuffsSliceImpl> {
   data: Vec<T>,
   inner: T::Native,
 }
 
+#[cfg(feature = "alloc")]
 impl<T: WuffsSliceImpl> WuffsSliceOwned<T> {
   pub fn new(mut data: Vec<T>) -> Self {
     let inner = T::from_ptr(data.as_mut_ptr(), data.len());
@@ -81,6 +88,15 @@ impl<T: WuffsSliceImpl> WuffsSliceOwned<T> {
   }
 }
 
+#[cfg(feature = "alloc")]
+impl<T: WuffsSliceImpl> Clone for WuffsSliceOwned<T> {
+  /// Clone the data and point the native slice at the copy rather than the original.
+  fn clone(&self) -> Self {
+    Self::new(self.data.clone())
+  }
+}
+
+#[cfg(feature = "alloc")]
 impl<T: WuffsSliceImpl> Deref for WuffsSliceOwned<T> {
   type Target = T::Native;
 
@@ -89,7 +105,6 @@ impl<T: WuffsSliceImpl> Deref for WuffsSliceOwned<T> {
   }
 }
 
-#[derive(Clone)]
 pub struct WuffsSliceBorrowed<'a, T: WuffsSliceImpl> {
   inner: T::Native,
   phantom: PhantomData<&'a u8>,
@@ -143,6 +158,23 @@ impl WuffsSliceImpl for u8 {
 
 impl WuffsSliceNative<u8> for wuffs_base__slice_u8 {
   fn data(&self) -> &[u8] {
-    unsafe { std::slice::from_raw_parts(self.ptr, self.len as _) }
+    unsafe { core::slice::from_raw_parts(self.ptr, self.len as _) }
+  }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use super::{WuffsSliceNative, WuffsSliceOwned};
+
+  #[test]
+  fn test_owned_clone() {
+    let owned = WuffsSliceOwned::new(vec![1u8, 2, 3]);
+    let clone = owned.clone();
+
+    assert_ne!(owned.ptr, clone.ptr);
+
+    drop(owned);
+
+    assert_eq!(clone.data(), &[1, 2, 3]);
   }
 }
@@ -1,5 +1,9 @@
-use std::{borrow::Cow, error::Error, ffi::CStr, fmt::Display, iter::FromIterator};
+use core::{
+  ffi::{c_char, CStr},
+  fmt::Display,
+};
 
+use crate::std::image::WuffsLimit;
 use wuffs_sys::{
   wuffs_base__note__end_of_data, wuffs_base__note__i_o_redirect,
   wuffs_base__note__metadata_reported, wuffs_base__status,
@@ -9,6 +13,14 @@ use wuffs_sys::{
   wuffs_base__suspension__short_write,
 };
 
+#[cfg(feature = "v0_4")]
+use wuffs_sys::{
+  wuffs_base__error__truncated_input, wuffs_base__suspension__short_workbuf,
+};
+
+/// Text of a status that has no dedicated variant, borrowed from the static C string.
+pub type WuffsMessage = &'static str;
+
 pub trait IntoResult<T> {
   fn into_result(self) -> Result<T, WuffsError>;
 }
@@ -33,14 +45,33 @@ pub enum WuffsStatus<T = ()> {
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum WuffsError {
-  Message(String),
+  Message(WuffsMessage),
   Note(WuffsNote),
   Suspension(WuffsSuspension),
+  /// Closed input ended before the decoder was done, reported since wuffs v0.4.
+  TruncatedInput,
+  /// A caller-supplied buffer holds `len` bytes but at least `needed` are required.
+  BufferTooSmall {
+    needed: usize,
+    len: usize,
+  },
+  /// A caller-supplied buffer doesn't start at a multiple of `align`.
+  BufferMisaligned {
+    align: usize,
+  },
+  /// The input doesn't start with the signature of a supported format.
+  UnknownFormat,
+  /// An image's `limit` is `value`, over the `max` it was given.
+  LimitExceeded {
+    limit: WuffsLimit,
+    value: u64,
+    max: u64,
+  },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum WuffsNote {
-  Other(String),
+  Other(WuffsMessage),
   IoRedirect,
   EndOfData,
   MetadataReported,
@@ -48,12 +79,14 @@ pub enum WuffsNote {
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum WuffsSuspension {
-  Other(String),
+  Other(WuffsMessage),
   EvenMoreInformation,
   MispositionedRead,
   MispositionedWrite,
   ShortRead,
   ShortWrite,
+  /// The work buffer is smaller than the stream needs, reported since wuffs v0.4.
+  ShortWorkbuf,
 }
 
 impl<T> WuffsStatus<T> {
@@ -76,20 +109,15 @@ impl<T> WuffsStatus<T> {
 
 impl From<wuffs_base__status> for WuffsStatus<()> {
   fn from(inner: wuffs_base__status) -> Self {
-    let repr = unsafe {
-      if inner.repr.is_null() {
-        Cow::from("")
-      } else {
-        CStr::from_ptr(inner.repr).to_string_lossy()
-      }
-    };
+    if inner.repr.is_null() {
+      return WuffsStatus::Ok(());
+    }
 
     unsafe {
-      let mut chars = repr.chars();
-      match chars.next() {
-        Some('$') => WuffsStatus::Suspension(WuffsSuspension::from_ptr(inner.repr)),
-        Some('#') => WuffsStatus::Err(chars.collect()),
-        Some('@') => WuffsStatus::Note(WuffsNote::from_ptr(inner.repr)),
+      match *inner.repr as u8 {
+        b'$' => WuffsStatus::Suspension(WuffsSuspension::from_ptr(inner.repr)),
+        b'#' => WuffsStatus::Err(WuffsError::from_ptr(inner.repr)),
+        b'@' => WuffsStatus::Note(WuffsNote::from_ptr(inner.repr)),
         _ => WuffsStatus::Ok(()),
       }
     }
@@ -97,7 +125,11 @@ impl From<wuffs_base__status> for WuffsStatus<()> {
 }
 
 impl WuffsSuspension {
-  pub unsafe fn from_ptr(ptr: *const i8) -> Self {
+  /// Map a status string to its variant.
+  ///
+  /// # Safety
+  /// `ptr` must be a valid, NUL terminated wuffs status string.
+  pub unsafe fn from_ptr(ptr: *const c_char) -> Self {
     if ptr == wuffs_base__suspension__even_more_information.as_ptr() {
       WuffsSuspension::EvenMoreInformation
     } else if ptr == wuffs_base__suspension__mispositioned_read.as_ptr() {
@@ -109,13 +141,22 @@ impl WuffsSuspension {
     } else if ptr == wuffs_base__suspension__short_write.as_ptr() {
       WuffsSuspension::ShortWrite
     } else {
-      WuffsSuspension::Other(CStr::from_ptr(ptr).to_string_lossy().to_string())
+      #[cfg(feature = "v0_4")]
+      if ptr == wuffs_base__suspension__short_workbuf.as_ptr() {
+        return WuffsSuspension::ShortWorkbuf;
+      }
+
+      WuffsSuspension::Other(message(ptr))
     }
   }
 }
 
 impl WuffsNote {
-  pub unsafe fn from_ptr(ptr: *const i8) -> Self {
+  /// Map a status string to its variant.
+  ///
+  /// # Safety
+  /// `ptr` must be a valid, NUL terminated wuffs status string.
+  pub unsafe fn from_ptr(ptr: *const c_char) -> Self {
     if ptr == wuffs_base__note__end_of_data.as_ptr() {
       WuffsNote::EndOfData
     } else if ptr == wuffs_base__note__i_o_redirect.as_ptr() {
@@ -123,21 +164,75 @@ impl WuffsNote {
     } else if ptr == wuffs_base__note__metadata_reported.as_ptr() {
       WuffsNote::MetadataReported
     } else {
-      WuffsNote::Other(CStr::from_ptr(ptr).to_string_lossy().to_string())
+      WuffsNote::Other(message(ptr))
     }
   }
 }
 
-impl FromIterator<char> for WuffsError {
-  fn from_iter<T: IntoIterator<Item = char>>(iter: T) -> Self {
-    Self::Message(String::from_iter(iter))
+impl WuffsError {
+  /// Map a status string to its variant.
+  ///
+  /// # Safety
+  /// `ptr` must be a valid, NUL terminated wuffs status string.
+  pub unsafe fn from_ptr(ptr: *const c_char) -> Self {
+    #[cfg(feature = "v0_4")]
+    if ptr == wuffs_base__error__truncated_input.as_ptr() {
+      return WuffsError::TruncatedInput;
+    }
+
+    // Drop the leading `#` that marks the status as an error.
+    WuffsError::Message(message(ptr.add(1)))
   }
 }
 
+/// # Safety
+/// `ptr` must be a valid, NUL terminated string that lives for the rest of the program,
+/// as wuffs status strings do.
+unsafe fn message(ptr: *const c_char) -> WuffsMessage {
+  // Status strings are ASCII, so this only fails on a corrupt pointer.
+  CStr::from_ptr(ptr)
+    .to_str()
+    .unwrap_or("invalid status message")
+}
+
 impl Display for WuffsError {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(f, "{}", self)
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::Message(message) => write!(f, "{}", message),
+      Self::Note(note) => write!(f, "unexpected note: {:?}", note),
+      Self::Suspension(suspension) => {
+        write!(f, "unexpected suspension: {:?}", suspension)
+      }
+      Self::TruncatedInput => write!(f, "base: truncated input"),
+      Self::BufferTooSmall { needed, len } => {
+        write!(f, "buffer holds {} bytes but {} are needed", len, needed)
+      }
+      Self::BufferMisaligned { align } => {
+        write!(f, "buffer is not aligned to {} bytes", align)
+      }
+      Self::UnknownFormat => write!(f, "unknown format"),
+      Self::LimitExceeded { limit, value, max } => {
+        write!(
+          f,
+          "image {} of {} exceeds the limit of {}",
+          limit, value, max
+        )
+      }
+    }
   }
 }
 
-impl Error for WuffsError {}
+#[cfg(feature = "std")]
+impl std::error::Error for WuffsError {}
+
+#[cfg(feature = "std")]
+impl From<WuffsError> for std::io::Error {
+  fn from(err: WuffsError) -> Self {
+    let kind = match err {
+      WuffsError::TruncatedInput => std::io::ErrorKind::UnexpectedEof,
+      _ => std::io::ErrorKind::InvalidData,
+    };
+
+    Self::new(kind, err)
+  }
+}
@@ -0,0 +1,66 @@
+io_decoder! {
+  /// Decoder for bzip2 streams.
+  WuffsBzip2Decoder, wuffs_bzip2__decoder {
+    sizeof: sizeof__wuffs_bzip2__decoder,
+    initialize: wuffs_bzip2__decoder__initialize,
+    set_quirk_enabled: wuffs_bzip2__decoder__set_quirk_enabled,
+    set_quirk: wuffs_bzip2__decoder__set_quirk,
+    transform_io: wuffs_bzip2__decoder__transform_io,
+    workbuf_len: WUFFS_BZIP2__DECODER_WORKBUF_LEN_MAX_INCL_WORST_CASE,
+  }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use crate::{
+    buf::WuffsBuf,
+    status::{WuffsError, WuffsSuspension},
+  };
+
+  // $ echo "Hello Wuffs." | bzip2 -9 | xxd
+  const SRC: [u8; 57] = [
+    0x42, 0x5a, 0x68, 0x39, 0x31, 0x41, 0x59, 0x26, // 00..07
+    0x53, 0x59, 0x19, 0x6e, 0x01, 0xfd, 0x00, 0x00, // 08..0F
+    0x01, 0xd7, 0x80, 0x00, 0x10, 0x40, 0x01, 0x00, // 10..17
+    0x40, 0x00, 0x80, 0x03, 0x04, 0x8a, 0x00, 0x20, // 18..1F
+    0x00, 0x22, 0x0d, 0x01, 0x90, 0x80, 0x69, 0xa6, // 20..27
+    0x81, 0xc6, 0x6a, 0xd1, 0x29, 0x82, 0xf1, 0x77, // 28..2F
+    0x24, 0x53, 0x85, 0x09, 0x01, 0x96, 0xe0, 0x1f, // 30..37
+    0xd0, // 38..38
+  ];
+
+  #[test]
+  fn test_bzip2_decode() {
+    let mut dst = [0; 1024];
+
+    let mut bz = super::WuffsBzip2Decoder::new().unwrap();
+    let (read, written) = bz.decode(&SRC, &mut dst).unwrap();
+
+    assert_eq!(read, SRC.len());
+    assert_eq!(&dst[..written], b"Hello Wuffs.\n");
+  }
+
+  #[test]
+  fn test_bzip2_fork() {
+    let mut head = [0; 4];
+    let mut src = unsafe { WuffsBuf::from_slice_readonly(&SRC) };
+    let mut dst = WuffsBuf::from_slice(&mut head);
+
+    let mut bz = super::WuffsBzip2Decoder::new().unwrap();
+    let err = bz.transform_io(&mut dst, &mut src).unwrap_err();
+
+    assert_eq!(err, WuffsError::Suspension(WuffsSuspension::ShortWrite));
+    assert_eq!(&head, b"Hell");
+
+    let mut fork = bz.fork();
+    let rest = &SRC[src.read()..];
+
+    let mut tail_a = [0; 1024];
+    let mut tail_b = [0; 1024];
+    let (_, written_a) = bz.decode(rest, &mut tail_a).unwrap();
+    let (_, written_b) = fork.decode(rest, &mut tail_b).unwrap();
+
+    assert_eq!(&tail_a[..written_a], b"o Wuffs.\n");
+    assert_eq!(&tail_b[..written_b], b"o Wuffs.\n");
+  }
+}
@@ -1,74 +1,63 @@
-use crate::{
-  boxed::{WuffsBox, WuffsBoxed},
-  buf::WuffsBuf,
-  slice::WuffsSlice,
-  status::{IntoResult, WuffsError},
-};
-use wuffs_sys::{
-  sizeof__wuffs_deflate__decoder, wuffs_deflate__decoder,
-  wuffs_deflate__decoder__initialize, wuffs_deflate__decoder__set_quirk_enabled,
-  wuffs_deflate__decoder__transform_io,
-  WUFFS_DEFLATE__DECODER_WORKBUF_LEN_MAX_INCL_WORST_CASE as WORK_BUF_SIZE, WUFFS_VERSION,
-};
-
-#[derive(Clone)]
-pub struct WuffsDeflateDecoder {
-  work: Vec<u8>,
-  inner: WuffsBox<wuffs_deflate__decoder>,
+io_decoder! {
+  /// Decoder for raw DEFLATE streams, as specified by RFC 1951.
+  WuffsDeflateDecoder, wuffs_deflate__decoder {
+    sizeof: sizeof__wuffs_deflate__decoder,
+    initialize: wuffs_deflate__decoder__initialize,
+    set_quirk_enabled: wuffs_deflate__decoder__set_quirk_enabled,
+    set_quirk: wuffs_deflate__decoder__set_quirk,
+    transform_io: wuffs_deflate__decoder__transform_io,
+    workbuf_len: WUFFS_DEFLATE__DECODER_WORKBUF_LEN_MAX_INCL_WORST_CASE,
+  }
 }
 
-impl WuffsDeflateDecoder {
-  pub fn new() -> Result<Self, WuffsError> {
-    let mut inner = WuffsBox::new();
-
-    unsafe {
-      wuffs_deflate__decoder__initialize(
-        inner.as_mut_ptr(),
-        inner.size() as _,
-        WUFFS_VERSION as _,
-        0x01,
-      )
-      .into_result()?;
-    }
-
-    Ok(Self {
-      work: vec![0; WORK_BUF_SIZE as _],
-      inner,
-    })
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use crate::{
+    buf::WuffsBuf,
+    status::{WuffsError, WuffsSuspension},
+  };
+
+  // SRC holds a raw DEFLATE-encoded "Hello Wuffs."
+  //
+  // $ echo "Hello Wuffs." | gzip --no-name | tail -c +11 | head -c -8 | xxd
+  // 00000000: f348 cdc9 c957 082f 4d4b 2bd6 e302 00    .H...W./MK+....
+  const SRC: [u8; 15] = [
+    0xf3, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0x08, 0x2f, // 00..07
+    0x4d, 0x4b, 0x2b, 0xd6, 0xe3, 0x02, 0x00, // 08..0E
+  ];
+
+  #[test]
+  fn test_deflate_decode() {
+    let mut dst = [0; 1024];
+
+    let mut inflate = super::WuffsDeflateDecoder::new().unwrap();
+    let (read, written) = inflate.decode(&SRC, &mut dst).unwrap();
+
+    assert_eq!(read, SRC.len());
+    assert_eq!(&dst[..written], b"Hello Wuffs.\n");
   }
 
-  pub fn set_quirk_enabled(&mut self, quirk: u32, enabled: bool) {
-    unsafe {
-      wuffs_deflate__decoder__set_quirk_enabled(self.inner.as_mut_ptr(), quirk, enabled);
-    }
-  }
+  #[test]
+  fn test_deflate_fork() {
+    let mut head = [0; 4];
+    let mut src = unsafe { WuffsBuf::from_slice_readonly(&SRC) };
+    let mut dst = WuffsBuf::from_slice(&mut head);
 
-  pub fn decode(
-    &mut self,
-    src: &[u8],
-    dst: &mut [u8],
-  ) -> Result<(usize, usize), WuffsError> {
-    let mut src = unsafe { WuffsBuf::from_slice_readonly(src) };
-    let mut dst = WuffsBuf::from_slice(dst);
+    let mut inflate = super::WuffsDeflateDecoder::new().unwrap();
+    let err = inflate.transform_io(&mut dst, &mut src).unwrap_err();
 
-    let work = WuffsSlice::from(&mut self.work[..]);
+    assert_eq!(err, WuffsError::Suspension(WuffsSuspension::ShortWrite));
+    assert_eq!(&head, b"Hell");
 
-    unsafe {
-      wuffs_deflate__decoder__transform_io(
-        self.inner.as_mut_ptr(),
-        dst.as_mut_ptr(),
-        src.as_mut_ptr(),
-        work.into_inner(),
-      )
-      .into_result()?
-    };
+    let mut fork = inflate.fork();
+    let rest = &SRC[src.read()..];
 
-    Ok((src.read(), dst.written()))
-  }
-}
+    let mut tail_a = [0; 1024];
+    let mut tail_b = [0; 1024];
+    let (_, written_a) = inflate.decode(rest, &mut tail_a).unwrap();
+    let (_, written_b) = fork.decode(rest, &mut tail_b).unwrap();
 
-impl WuffsBoxed for wuffs_deflate__decoder {
-  fn size() -> usize {
-    unsafe { sizeof__wuffs_deflate__decoder() as _ }
+    assert_eq!(&tail_a[..written_a], b"o Wuffs.\n");
+    assert_eq!(&tail_b[..written_b], b"o Wuffs.\n");
   }
 }
@@ -1,99 +1,43 @@
-use crate::{
-  boxed::{WuffsBox, WuffsBoxed},
-  buf::WuffsBuf,
-  slice::WuffsSlice,
-  status::{IntoResult, WuffsError},
-};
-use wuffs_sys::{
-  sizeof__wuffs_gzip__decoder, wuffs_gzip__decoder, wuffs_gzip__decoder__initialize,
-  wuffs_gzip__decoder__set_quirk_enabled, wuffs_gzip__decoder__transform_io,
-  WUFFS_GZIP__DECODER_WORKBUF_LEN_MAX_INCL_WORST_CASE as WORK_BUF_SIZE, WUFFS_VERSION,
-};
-
-#[derive(Clone)]
-pub struct WuffsGzipDecoder {
-  work: Vec<u8>,
-  inner: WuffsBox<wuffs_gzip__decoder>,
-}
-
-impl WuffsGzipDecoder {
-  pub fn new() -> Result<Self, WuffsError> {
-    let mut inner = WuffsBox::new();
-
-    unsafe {
-      wuffs_gzip__decoder__initialize(
-        inner.as_mut_ptr(),
-        inner.size() as _,
-        WUFFS_VERSION as _,
-        0x01,
-      )
-      .into_result()?;
-    }
-
-    Ok(Self {
-      work: vec![0; WORK_BUF_SIZE as _],
-      inner,
-    })
-  }
-
-  pub fn set_quirk_enabled(&mut self, quirk: u32, enabled: bool) {
-    unsafe {
-      wuffs_gzip__decoder__set_quirk_enabled(self.inner.as_mut_ptr(), quirk, enabled);
-    }
-  }
-
-  pub fn decode(
-    &mut self,
-    src: &[u8],
-    dst: &mut [u8],
-  ) -> Result<(usize, usize), WuffsError> {
-    let mut src = unsafe { WuffsBuf::from_slice_readonly(src) };
-    let mut dst = WuffsBuf::from_slice(dst);
-
-    let work = WuffsSlice::from(&mut self.work[..]);
-
-    unsafe {
-      wuffs_gzip__decoder__transform_io(
-        self.inner.as_mut_ptr(),
-        dst.as_mut_ptr(),
-        src.as_mut_ptr(),
-        work.into_inner(),
-      )
-      .into_result()?
-    };
-
-    Ok((src.read(), dst.written()))
+io_decoder! {
+  /// Decoder for gzip streams, as specified by RFC 1952.
+  WuffsGzipDecoder, wuffs_gzip__decoder {
+    sizeof: sizeof__wuffs_gzip__decoder,
+    initialize: wuffs_gzip__decoder__initialize,
+    set_quirk_enabled: wuffs_gzip__decoder__set_quirk_enabled,
+    set_quirk: wuffs_gzip__decoder__set_quirk,
+    transform_io: wuffs_gzip__decoder__transform_io,
+    workbuf_len: WUFFS_GZIP__DECODER_WORKBUF_LEN_MAX_INCL_WORST_CASE,
   }
 }
 
-impl WuffsBoxed for wuffs_gzip__decoder {
-  fn size() -> usize {
-    unsafe { sizeof__wuffs_gzip__decoder() as _ }
-  }
-}
-
-#[cfg(test)]
+#[cfg(all(test, feature = "alloc"))]
 mod tests {
+  use crate::{
+    buf::WuffsBuf,
+    status::{WuffsError, WuffsSuspension},
+  };
+
+  // SRC holds a gzip-encoded "Hello Wuffs."
+  //
+  // $ echo "Hello Wuffs." | gzip --no-name | xxd
+  // 00000000: 1f8b 0800 0000 0000 0003 f348 cdc9 c957  ...........H...W
+  // 00000010: 082f 4d4b 2bd6 e302 003c 8475 bb0d 0000  ./MK+....<.u....
+  // 00000020: 00                                       .
+  //
+  // Passing --no-name to the gzip command line also means to skip the timestamp,
+  // which means that its output is deterministic.
+  const SRC: [u8; 33] = [
+    0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, // 00..07
+    0x00, 0x03, 0xf3, 0x48, 0xcd, 0xc9, 0xc9, 0x57, // 08..0F
+    0x08, 0x2f, 0x4d, 0x4b, 0x2b, 0xd6, 0xe3, 0x02, // 10..17
+    0x00, 0x3c, 0x84, 0x75, 0xbb, 0x0d, 0x00, 0x00, // 18..1F
+    0x00, // 20..20
+  ];
+
   #[test]
   fn test_gzip_decode() {
     let cmp = b"Hello Wuffs.\n";
-
-    // src holds a gzip-encoded "Hello Wuffs."
-    //
-    // $ echo "Hello Wuffs." | gzip --no-name | xxd
-    // 00000000: 1f8b 0800 0000 0000 0003 f348 cdc9 c957  ...........H...W
-    // 00000010: 082f 4d4b 2bd6 e302 003c 8475 bb0d 0000  ./MK+....<.u....
-    // 00000020: 00                                       .
-    //
-    // Passing --no-name to the gzip command line also means to skip the timestamp,
-    // which means that its output is deterministic.
-    let src = [
-      0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, // 00..07
-      0x00, 0x03, 0xf3, 0x48, 0xcd, 0xc9, 0xc9, 0x57, // 08..0F
-      0x08, 0x2f, 0x4d, 0x4b, 0x2b, 0xd6, 0xe3, 0x02, // 10..17
-      0x00, 0x3c, 0x84, 0x75, 0xbb, 0x0d, 0x00, 0x00, // 18..1F
-      0x00, // 20..20
-    ];
+    let src = SRC;
 
     let mut dst = [0; 1024];
 
@@ -107,4 +51,47 @@ mod tests {
 
     assert_eq!(dst, b"Hello Wuffs.\n");
   }
+
+  #[test]
+  fn test_gzip_fork() {
+    let mut head = [0; 4];
+    let mut src = unsafe { WuffsBuf::from_slice_readonly(&SRC) };
+    let mut dst = WuffsBuf::from_slice(&mut head);
+
+    let mut gz = super::WuffsGzipDecoder::new().unwrap();
+    let err = gz.transform_io(&mut dst, &mut src).unwrap_err();
+
+    assert_eq!(err, WuffsError::Suspension(WuffsSuspension::ShortWrite));
+    assert_eq!(&head, b"Hell");
+
+    let mut fork = gz.fork();
+    let rest = &SRC[src.read()..];
+
+    let mut tail_a = [0; 1024];
+    let mut tail_b = [0; 1024];
+    let (_, written_a) = gz.decode(rest, &mut tail_a).unwrap();
+    let (_, written_b) = fork.decode(rest, &mut tail_b).unwrap();
+
+    assert_eq!(&tail_a[..written_a], b"o Wuffs.\n");
+    assert_eq!(&tail_b[..written_b], b"o Wuffs.\n");
+  }
+
+  #[test]
+  fn test_gzip_from_buffers() {
+    use crate::boxed::WuffsBoxed;
+    use wuffs_sys::wuffs_gzip__decoder;
+
+    let layout = wuffs_gzip__decoder::layout();
+    let mut words = vec![0u64; layout.size() / 8 + 1];
+    let state = unsafe {
+      std::slice::from_raw_parts_mut(words.as_mut_ptr() as *mut u8, layout.size())
+    };
+    let mut work = [0; super::WuffsGzipDecoder::WORK_BUF_LEN];
+
+    let mut dst = [0; 1024];
+    let mut gz = super::WuffsGzipDecoder::from_buffers(state, &mut work).unwrap();
+    let (_, written) = gz.decode(&SRC, &mut dst).unwrap();
+
+    assert_eq!(&dst[..written], b"Hello Wuffs.\n");
+  }
 }
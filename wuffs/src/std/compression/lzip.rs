@@ -0,0 +1,83 @@
+io_decoder! {
+  /// Decoder for lzip streams.  The work buffer holds the stream's dictionary, whose size
+  /// is only known once its header is read.
+  WuffsLzipDecoder<W: WuffsWork>, wuffs_lzip__decoder {
+    sizeof: sizeof__wuffs_lzip__decoder,
+    initialize: wuffs_lzip__decoder__initialize,
+    set_quirk_enabled: wuffs_lzip__decoder__set_quirk_enabled,
+    set_quirk: wuffs_lzip__decoder__set_quirk,
+    transform_io: wuffs_lzip__decoder__transform_io,
+    workbuf_len: wuffs_lzip__decoder__workbuf_len,
+  }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use crate::{
+    buf::WuffsBuf,
+    status::{WuffsError, WuffsSuspension},
+  };
+
+  // SRC holds the LZMA stream of "Hello Wuffs." from the lzma tests, behind the lzip
+  // magic, version 1 and a 4 KiB dictionary size, and followed by the CRC-32, the data
+  // size and the member size.
+  const SRC: [u8; 49] = [
+    0x4c, 0x5a, 0x49, 0x50, 0x01, 0x0c, 0x00, 0x24, // 00..07
+    0x19, 0x49, 0x98, 0x6f, 0x10, 0x11, 0xc9, 0x25, // 08..0F
+    0x3b, 0xce, 0x73, 0x27, 0x44, 0x18, 0xf6, 0x5f, // 10..17
+    0xff, 0xf5, 0xa0, 0x40, 0x00, 0x3c, 0x84, 0x75, // 18..1F
+    0xbb, 0x0d, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // 20..27
+    0x00, 0x31, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // 28..2F
+    0x00, // 30..30
+  ];
+
+  #[test]
+  fn test_lzip_decode() {
+    let mut dst = [0; 1024];
+
+    let mut lz = super::WuffsLzipDecoder::new().unwrap();
+    let (read, written) = lz.decode(&SRC, &mut dst).unwrap();
+
+    assert_eq!(read, SRC.len());
+    assert_eq!(&dst[..written], b"Hello Wuffs.\n");
+    assert!(lz.workbuf_len() > 0);
+  }
+
+  #[test]
+  fn test_lzip_fork() {
+    let mut head = [0; 4];
+    let mut src = unsafe { WuffsBuf::from_slice_readonly(&SRC) };
+    let mut dst = WuffsBuf::from_slice(&mut head);
+
+    let mut lz = super::WuffsLzipDecoder::new().unwrap();
+    let err = lz.transform_io(&mut dst, &mut src).unwrap_err();
+
+    assert_eq!(err, WuffsError::Suspension(WuffsSuspension::ShortWrite));
+    assert_eq!(&head, b"Hell");
+
+    let mut fork = lz.fork();
+    let rest = &SRC[src.read()..];
+
+    let mut tail_a = [0; 1024];
+    let mut tail_b = [0; 1024];
+    let (_, written_a) = lz.decode(rest, &mut tail_a).unwrap();
+    let (_, written_b) = fork.decode(rest, &mut tail_b).unwrap();
+
+    assert_eq!(&tail_a[..written_a], b"o Wuffs.\n");
+    assert_eq!(&tail_b[..written_b], b"o Wuffs.\n");
+  }
+
+  #[test]
+  fn test_lzip_short_work() {
+    use crate::boxed::WuffsInline;
+
+    let mut dst = [0; 1024];
+    let mut work = [0; 16];
+
+    let mut lz =
+      super::WuffsLzipDecoder::with_work(WuffsInline::new(), &mut work[..]).unwrap();
+    let err = lz.decode(&SRC, &mut dst).unwrap_err();
+
+    assert!(matches!(err, WuffsError::BufferTooSmall { len: 16, .. }));
+  }
+}
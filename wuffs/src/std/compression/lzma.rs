@@ -0,0 +1,82 @@
+io_decoder! {
+  /// Decoder for LZMA streams in the `.lzma` format, also known as LZMA-alone.  The work
+  /// buffer holds the stream's dictionary, whose size is only known once its header is
+  /// read.
+  WuffsLzmaDecoder<W: WuffsWork>, wuffs_lzma__decoder {
+    sizeof: sizeof__wuffs_lzma__decoder,
+    initialize: wuffs_lzma__decoder__initialize,
+    set_quirk_enabled: wuffs_lzma__decoder__set_quirk_enabled,
+    set_quirk: wuffs_lzma__decoder__set_quirk,
+    transform_io: wuffs_lzma__decoder__transform_io,
+    workbuf_len: wuffs_lzma__decoder__workbuf_len,
+  }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use crate::{
+    buf::WuffsBuf,
+    status::{WuffsError, WuffsSuspension},
+  };
+
+  // SRC holds an LZMA-encoded "Hello Wuffs." with a 4 KiB dictionary.
+  //
+  // $ echo "Hello Wuffs." | xz --format=lzma --lzma1=dict=4KiB | xxd
+  const SRC: [u8; 36] = [
+    0x5d, 0x00, 0x10, 0x00, 0x00, 0xff, 0xff, 0xff, // 00..07
+    0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x24, 0x19, // 08..0F
+    0x49, 0x98, 0x6f, 0x10, 0x11, 0xc9, 0x25, 0x3b, // 10..17
+    0xce, 0x73, 0x27, 0x44, 0x18, 0xf6, 0x5f, 0xff, // 18..1F
+    0xf5, 0xa0, 0x40, 0x00, // 20..23
+  ];
+
+  #[test]
+  fn test_lzma_decode() {
+    let mut dst = [0; 1024];
+
+    let mut lzma = super::WuffsLzmaDecoder::new().unwrap();
+    let (read, written) = lzma.decode(&SRC, &mut dst).unwrap();
+
+    assert_eq!(read, SRC.len());
+    assert_eq!(&dst[..written], b"Hello Wuffs.\n");
+    assert!(lzma.workbuf_len() > 0);
+  }
+
+  #[test]
+  fn test_lzma_fork() {
+    let mut head = [0; 4];
+    let mut src = unsafe { WuffsBuf::from_slice_readonly(&SRC) };
+    let mut dst = WuffsBuf::from_slice(&mut head);
+
+    let mut lzma = super::WuffsLzmaDecoder::new().unwrap();
+    let err = lzma.transform_io(&mut dst, &mut src).unwrap_err();
+
+    assert_eq!(err, WuffsError::Suspension(WuffsSuspension::ShortWrite));
+    assert_eq!(&head, b"Hell");
+
+    let mut fork = lzma.fork();
+    let rest = &SRC[src.read()..];
+
+    let mut tail_a = [0; 1024];
+    let mut tail_b = [0; 1024];
+    let (_, written_a) = lzma.decode(rest, &mut tail_a).unwrap();
+    let (_, written_b) = fork.decode(rest, &mut tail_b).unwrap();
+
+    assert_eq!(&tail_a[..written_a], b"o Wuffs.\n");
+    assert_eq!(&tail_b[..written_b], b"o Wuffs.\n");
+  }
+
+  #[test]
+  fn test_lzma_short_work() {
+    use crate::boxed::WuffsInline;
+
+    let mut dst = [0; 1024];
+    let mut work = [0; 16];
+
+    let mut lzma =
+      super::WuffsLzmaDecoder::with_work(WuffsInline::new(), &mut work[..]).unwrap();
+    let err = lzma.decode(&SRC, &mut dst).unwrap_err();
+
+    assert!(matches!(err, WuffsError::BufferTooSmall { len: 16, .. }));
+  }
+}
@@ -1,73 +1,62 @@
-use crate::{
-  boxed::{WuffsBox, WuffsBoxed},
-  buf::WuffsBuf,
-  slice::WuffsSlice,
-  status::{IntoResult, WuffsError},
-};
-use wuffs_sys::{
-  sizeof__wuffs_lzw__decoder, wuffs_lzw__decoder, wuffs_lzw__decoder__initialize,
-  wuffs_lzw__decoder__set_quirk_enabled, wuffs_lzw__decoder__transform_io,
-  WUFFS_LZW__DECODER_WORKBUF_LEN_MAX_INCL_WORST_CASE as WORK_BUF_SIZE, WUFFS_VERSION,
-};
-
-#[derive(Clone)]
-pub struct WuffsLzwDecoder {
-  work: Vec<u8>,
-  inner: WuffsBox<wuffs_lzw__decoder>,
+io_decoder! {
+  /// Decoder for the LZW variant used by GIF.
+  WuffsLzwDecoder, wuffs_lzw__decoder {
+    sizeof: sizeof__wuffs_lzw__decoder,
+    initialize: wuffs_lzw__decoder__initialize,
+    set_quirk_enabled: wuffs_lzw__decoder__set_quirk_enabled,
+    set_quirk: wuffs_lzw__decoder__set_quirk,
+    transform_io: wuffs_lzw__decoder__transform_io,
+    workbuf_len: WUFFS_LZW__DECODER_WORKBUF_LEN_MAX_INCL_WORST_CASE,
+  }
 }
 
-impl WuffsLzwDecoder {
-  pub fn new() -> Result<Self, WuffsError> {
-    let mut inner = WuffsBox::new();
-
-    unsafe {
-      wuffs_lzw__decoder__initialize(
-        inner.as_mut_ptr(),
-        inner.size() as _,
-        WUFFS_VERSION as _,
-        0x01,
-      )
-      .into_result()?;
-    }
-
-    Ok(Self {
-      work: vec![0; WORK_BUF_SIZE as _],
-      inner,
-    })
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use crate::{
+    buf::WuffsBuf,
+    status::{WuffsError, WuffsSuspension},
+  };
+
+  // SRC holds "Hello Wuffs." as GIF-style LZW with 8-bit literals: a clear code, one
+  // literal code per byte and an end code, 9 bits each and least significant bit first.
+  const SRC: [u8; 17] = [
+    0x00, 0x91, 0x94, 0x61, 0xc3, 0xe6, 0x0d, 0x88, // 00..07
+    0x2b, 0x75, 0xcc, 0x98, 0x99, 0xe3, 0x42, 0x41, // 08..0F
+    0x40, // 10..10
+  ];
+
+  #[test]
+  fn test_lzw_decode() {
+    let mut dst = [0; 1024];
+
+    let mut lzw = super::WuffsLzwDecoder::new().unwrap();
+    let (read, written) = lzw.decode(&SRC, &mut dst).unwrap();
+
+    assert_eq!(read, SRC.len());
+    assert_eq!(&dst[..written], b"Hello Wuffs.\n");
   }
 
-  pub fn set_quirk_enabled(&mut self, quirk: u32, enabled: bool) {
-    unsafe {
-      wuffs_lzw__decoder__set_quirk_enabled(self.inner.as_mut_ptr(), quirk, enabled);
-    }
-  }
+  #[test]
+  fn test_lzw_fork() {
+    let mut head = [0; 4];
+    let mut src = unsafe { WuffsBuf::from_slice_readonly(&SRC) };
+    let mut dst = WuffsBuf::from_slice(&mut head);
 
-  pub fn decode(
-    &mut self,
-    src: &[u8],
-    dst: &mut [u8],
-  ) -> Result<(usize, usize), WuffsError> {
-    let mut src = unsafe { WuffsBuf::from_slice_readonly(src) };
-    let mut dst = WuffsBuf::from_slice(dst);
+    let mut lzw = super::WuffsLzwDecoder::new().unwrap();
+    let err = lzw.transform_io(&mut dst, &mut src).unwrap_err();
 
-    let work = WuffsSlice::from(&mut self.work[..]);
+    assert_eq!(err, WuffsError::Suspension(WuffsSuspension::ShortWrite));
+    assert_eq!(&head, b"Hell");
 
-    unsafe {
-      wuffs_lzw__decoder__transform_io(
-        self.inner.as_mut_ptr(),
-        dst.as_mut_ptr(),
-        src.as_mut_ptr(),
-        work.into_inner(),
-      )
-      .into_result()?
-    };
+    let mut fork = lzw.fork();
+    let rest = &SRC[src.read()..];
 
-    Ok((src.read(), dst.written()))
-  }
-}
+    let mut tail_a = [0; 1024];
+    let mut tail_b = [0; 1024];
+    let (_, written_a) = lzw.decode(rest, &mut tail_a).unwrap();
+    let (_, written_b) = fork.decode(rest, &mut tail_b).unwrap();
 
-impl WuffsBoxed for wuffs_lzw__decoder {
-  fn size() -> usize {
-    unsafe { sizeof__wuffs_lzw__decoder() as _ }
+    assert_eq!(&tail_a[..written_a], b"o Wuffs.\n");
+    assert_eq!(&tail_b[..written_b], b"o Wuffs.\n");
   }
 }
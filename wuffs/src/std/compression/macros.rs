@@ -0,0 +1,327 @@
+/// Define the decoder `$decoder` over the wuffs state `$state`, with its constructors,
+/// the quirk setters, `decode`, `transform_io`, `fork` and the storage traits, by calling
+/// the listed `wuffs_sys` functions.
+///
+/// Given the `workbuf_len` constant, the work buffer is a fixed number of bytes, wuffs'
+/// worst case for the decoder.  Given `<W: WuffsWork>` and the `workbuf_len` function
+/// instead, it grows to what the stream asks for, such as an LZMA dictionary.
+macro_rules! io_decoder {
+  (
+    $(#[$attr:meta])*
+    $decoder:ident, $state:ident {
+      sizeof: $sizeof:ident,
+      initialize: $initialize:ident,
+      set_quirk_enabled: $set_quirk_enabled:ident,
+      set_quirk: $set_quirk:ident,
+      transform_io: $transform_io:ident,
+      workbuf_len: $workbuf_len:ident,
+    }
+  ) => {
+    $(#[$attr])*
+    pub struct $decoder<
+      B = $crate::boxed::WuffsDefault<::wuffs_sys::$state>,
+      W = [u8; ::wuffs_sys::$workbuf_len as usize],
+    > {
+      work: W,
+      inner: B,
+    }
+
+    impl $decoder {
+      /// Bytes of work buffer the decoder needs, see [`with_work`](Self::with_work).
+      pub const WORK_BUF_LEN: usize = ::wuffs_sys::$workbuf_len as usize;
+
+      #[cfg(feature = "alloc")]
+      pub fn new() -> Result<Self, $crate::status::WuffsError> {
+        Self::with_storage($crate::boxed::WuffsDefault::default())
+      }
+    }
+
+    impl<B: $crate::boxed::WuffsStorage<::wuffs_sys::$state>> $decoder<B> {
+      /// Initialize a decoder whose state lives in `inner`.
+      pub fn with_storage(inner: B) -> Result<Self, $crate::status::WuffsError> {
+        Self::with_work(inner, [0; ::wuffs_sys::$workbuf_len as usize])
+      }
+    }
+
+    impl<'a>
+      $decoder<$crate::boxed::WuffsBorrowed<'a, ::wuffs_sys::$state>, &'a mut [u8]>
+    {
+      /// Initialize a decoder without allocating: the state lives in `state`, which
+      /// must satisfy [`WuffsBorrowed::new`](crate::boxed::WuffsBorrowed::new), and
+      #[doc = concat!(
+        "`work` must hold at least [`WORK_BUF_LEN`](", stringify!($decoder),
+        "::WORK_BUF_LEN) bytes."
+      )]
+      pub fn from_buffers(
+        state: &'a mut [u8],
+        work: &'a mut [u8],
+      ) -> Result<Self, $crate::status::WuffsError> {
+        Self::with_work($crate::boxed::WuffsBorrowed::new(state)?, work)
+      }
+    }
+
+    impl<B: $crate::boxed::WuffsStorage<::wuffs_sys::$state>, W: AsMut<[u8]>>
+      $decoder<B, W>
+    {
+      /// Initialize a decoder whose state lives in `inner` and whose work buffer is
+      #[doc = concat!(
+        "`work`, which must hold at least [`WORK_BUF_LEN`](", stringify!($decoder),
+        "::WORK_BUF_LEN) bytes."
+      )]
+      pub fn with_work(
+        inner: B,
+        mut work: W,
+      ) -> Result<Self, $crate::status::WuffsError> {
+        $crate::boxed::check_len(
+          work.as_mut().len(),
+          ::wuffs_sys::$workbuf_len as usize,
+        )?;
+
+        Self::initialize(inner, work)
+      }
+
+      /// Decode from `src` into `dst`.  Unlike [`decode`](Self::decode) the read and
+      /// write positions of both buffers are kept when the decoder suspends.
+      pub fn transform_io(
+        &mut self,
+        dst: &mut $crate::buf::WuffsBuf,
+        src: &mut $crate::buf::WuffsBuf,
+      ) -> Result<(), $crate::status::WuffsError> {
+        use $crate::status::IntoResult;
+
+        let work = $crate::slice::WuffsSlice::from(self.work.as_mut());
+
+        unsafe {
+          ::wuffs_sys::$transform_io(
+            self.inner.as_mut_ptr(),
+            dst.as_mut_ptr(),
+            src.as_mut_ptr(),
+            work.into_inner(),
+          )
+          .into_result()
+        }
+      }
+    }
+
+    io_decoder! {
+      @common $decoder<W: AsMut<[u8]>>, $state {
+        sizeof: $sizeof,
+        initialize: $initialize,
+        set_quirk_enabled: $set_quirk_enabled,
+        set_quirk: $set_quirk,
+      }
+    }
+  };
+
+  (
+    $(#[$attr:meta])*
+    $decoder:ident<W: WuffsWork>, $state:ident {
+      sizeof: $sizeof:ident,
+      initialize: $initialize:ident,
+      set_quirk_enabled: $set_quirk_enabled:ident,
+      set_quirk: $set_quirk:ident,
+      transform_io: $transform_io:ident,
+      workbuf_len: $workbuf_len:ident,
+    }
+  ) => {
+    $(#[$attr])*
+    pub struct $decoder<
+      B = $crate::boxed::WuffsDefault<::wuffs_sys::$state>,
+      W = $crate::boxed::WuffsHeapWork,
+    > {
+      work: W,
+      inner: B,
+    }
+
+    impl $decoder {
+      #[cfg(feature = "alloc")]
+      pub fn new() -> Result<Self, $crate::status::WuffsError> {
+        Self::with_storage($crate::boxed::WuffsDefault::default())
+      }
+    }
+
+    impl<B: $crate::boxed::WuffsStorage<::wuffs_sys::$state>> $decoder<B> {
+      /// Initialize a decoder whose state lives in `inner`, with a heap work buffer.
+      #[cfg(feature = "alloc")]
+      pub fn with_storage(inner: B) -> Result<Self, $crate::status::WuffsError> {
+        Self::with_work(inner, $crate::boxed::WuffsHeapWork::default())
+      }
+    }
+
+    impl<'a>
+      $decoder<$crate::boxed::WuffsBorrowed<'a, ::wuffs_sys::$state>, &'a mut [u8]>
+    {
+      /// Initialize a decoder without allocating: the state lives in `state`, which
+      /// must satisfy [`WuffsBorrowed::new`](crate::boxed::WuffsBorrowed::new), and
+      /// `work` must hold the [`workbuf_len`](Self::workbuf_len) of the streams it
+      /// decodes.
+      pub fn from_buffers(
+        state: &'a mut [u8],
+        work: &'a mut [u8],
+      ) -> Result<Self, $crate::status::WuffsError> {
+        Self::with_work($crate::boxed::WuffsBorrowed::new(state)?, work)
+      }
+    }
+
+    impl<B, W> $decoder<B, W>
+    where
+      B: $crate::boxed::WuffsStorage<::wuffs_sys::$state>,
+      W: $crate::boxed::WuffsWork,
+    {
+      /// Initialize a decoder whose state lives in `inner` and whose work buffer is
+      /// `work`.  A heap work buffer grows to fit; a slice must already be large enough
+      /// for the streams it decodes.
+      pub fn with_work(inner: B, work: W) -> Result<Self, $crate::status::WuffsError> {
+        Self::initialize(inner, work)
+      }
+
+      /// Bytes of work buffer the decoder needs, known once the stream header is read.
+      pub fn workbuf_len(&self) -> usize {
+        unsafe { ::wuffs_sys::$workbuf_len(self.inner.as_ptr()).min_incl as _ }
+      }
+
+      /// Decode from `src` into `dst`.  Unlike [`decode`](Self::decode) the read and
+      /// write positions of both buffers are kept when the decoder suspends.
+      pub fn transform_io(
+        &mut self,
+        dst: &mut $crate::buf::WuffsBuf,
+        src: &mut $crate::buf::WuffsBuf,
+      ) -> Result<(), $crate::status::WuffsError> {
+        use $crate::status::{IntoResult, WuffsError, WuffsSuspension};
+
+        loop {
+          let needed = self.workbuf_len();
+          let work = self.work.reserve(needed)?;
+          let len = work.len();
+          let work = $crate::slice::WuffsSlice::from(work);
+
+          let status = unsafe {
+            ::wuffs_sys::$transform_io(
+              self.inner.as_mut_ptr(),
+              dst.as_mut_ptr(),
+              src.as_mut_ptr(),
+              work.into_inner(),
+            )
+            .into_result()
+          };
+
+          // The stream header states how much work buffer the rest of it needs, so
+          // grow to that and carry on where the decoder stopped.
+          match status {
+            Err(WuffsError::Suspension(WuffsSuspension::ShortWorkbuf))
+              if self.workbuf_len() > len => {}
+            status => return status,
+          }
+        }
+      }
+    }
+
+    io_decoder! {
+      @common $decoder<W: $crate::boxed::WuffsWork>, $state {
+        sizeof: $sizeof,
+        initialize: $initialize,
+        set_quirk_enabled: $set_quirk_enabled,
+        set_quirk: $set_quirk,
+      }
+    }
+  };
+
+  (
+    @common $decoder:ident<W: $work:path>, $state:ident {
+      sizeof: $sizeof:ident,
+      initialize: $initialize:ident,
+      set_quirk_enabled: $set_quirk_enabled:ident,
+      set_quirk: $set_quirk:ident,
+    }
+  ) => {
+    impl<B: $crate::boxed::WuffsStorage<::wuffs_sys::$state>, W: $work> $decoder<B, W> {
+      fn initialize(mut inner: B, work: W) -> Result<Self, $crate::status::WuffsError> {
+        use $crate::status::IntoResult;
+
+        unsafe {
+          ::wuffs_sys::$initialize(
+            inner.as_mut_ptr(),
+            inner.size() as _,
+            ::wuffs_sys::WUFFS_VERSION as _,
+            0,
+          )
+          .into_result()?;
+        }
+
+        Ok(Self { work, inner })
+      }
+
+      /// Turn `quirk` on or off.  wuffs v0.4 replaced boolean quirks with `set_quirk`,
+      /// available with the `v0_4` feature, which also reports quirks the decoder doesn't
+      /// support; they are ignored here.
+      pub fn set_quirk_enabled(&mut self, quirk: u32, enabled: bool) {
+        #[cfg(not(feature = "v0_4"))]
+        unsafe {
+          ::wuffs_sys::$set_quirk_enabled(self.inner.as_mut_ptr(), quirk, enabled);
+        }
+
+        #[cfg(feature = "v0_4")]
+        let _ = self.set_quirk(quirk, enabled as u64);
+      }
+
+      /// Set the quirk `key` to `value`, failing if the decoder doesn't support it.
+      #[cfg(feature = "v0_4")]
+      pub fn set_quirk(
+        &mut self,
+        key: u32,
+        value: u64,
+      ) -> Result<(), $crate::status::WuffsError> {
+        use $crate::status::IntoResult;
+
+        unsafe {
+          ::wuffs_sys::$set_quirk(self.inner.as_mut_ptr(), key, value).into_result()
+        }
+      }
+
+      pub fn decode(
+        &mut self,
+        src: &[u8],
+        dst: &mut [u8],
+      ) -> Result<(usize, usize), $crate::status::WuffsError> {
+        let mut src = unsafe { $crate::buf::WuffsBuf::from_slice_readonly(src) };
+        let mut dst = $crate::buf::WuffsBuf::from_slice(dst);
+
+        self.transform_io(&mut dst, &mut src)?;
+
+        Ok((src.read(), dst.written()))
+      }
+    }
+
+    impl<B, W> $decoder<B, W>
+    where
+      B: $crate::boxed::WuffsStorage<::wuffs_sys::$state> + Clone,
+      W: $work + Clone,
+    {
+      /// Snapshot the decoder and its work buffer, see [forking](super#forking).
+      pub fn fork(&self) -> Self {
+        Self {
+          work: self.work.clone(),
+          inner: self.inner.clone(),
+        }
+      }
+    }
+
+    impl<B, W> Clone for $decoder<B, W>
+    where
+      B: $crate::boxed::WuffsStorage<::wuffs_sys::$state> + Clone,
+      W: $work + Clone,
+    {
+      fn clone(&self) -> Self {
+        self.fork()
+      }
+    }
+
+    impl $crate::boxed::WuffsBoxed for ::wuffs_sys::$state {
+      fn size() -> usize {
+        unsafe { ::wuffs_sys::$sizeof() as _ }
+      }
+    }
+
+    unsafe impl $crate::boxed::WuffsSized for ::wuffs_sys::$state {}
+  };
+}
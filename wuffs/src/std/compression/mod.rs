@@ -1,4 +1,39 @@
+//! Decompressors built on wuffs' `transform_io`.
+//!
+//! # Forking
+//!
+//! Every decoder has a `fork` that snapshots it, work buffer included, so that both
+//! copies can carry on decoding independently from the current position.  wuffs keeps
+//! all of its coroutine state inside the decoder and never holds on to pointers into
+//! itself or into the caller's buffers between calls, so copying the state bytes is
+//! enough, even mid-stream.
+
+#[cfg(any(
+  feature = "bzip2",
+  feature = "deflate",
+  feature = "gzip",
+  feature = "lzip",
+  feature = "lzma",
+  feature = "lzw",
+  feature = "xz",
+  feature = "zlib"
+))]
+#[macro_use]
+mod macros;
+
+#[cfg(feature = "bzip2")]
+pub mod bzip2;
+#[cfg(feature = "deflate")]
 pub mod deflate;
+#[cfg(feature = "gzip")]
 pub mod gzip;
+#[cfg(feature = "lzip")]
+pub mod lzip;
+#[cfg(feature = "lzma")]
+pub mod lzma;
+#[cfg(feature = "lzw")]
 pub mod lzw;
+#[cfg(feature = "xz")]
+pub mod xz;
+#[cfg(feature = "zlib")]
 pub mod zlib;
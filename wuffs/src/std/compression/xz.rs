@@ -0,0 +1,86 @@
+io_decoder! {
+  /// Decoder for XZ streams.  The work buffer holds the stream's dictionary, whose size
+  /// is only known once its header is read.
+  WuffsXzDecoder<W: WuffsWork>, wuffs_xz__decoder {
+    sizeof: sizeof__wuffs_xz__decoder,
+    initialize: wuffs_xz__decoder__initialize,
+    set_quirk_enabled: wuffs_xz__decoder__set_quirk_enabled,
+    set_quirk: wuffs_xz__decoder__set_quirk,
+    transform_io: wuffs_xz__decoder__transform_io,
+    workbuf_len: wuffs_xz__decoder__workbuf_len,
+  }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use crate::{
+    buf::WuffsBuf,
+    status::{WuffsError, WuffsSuspension},
+  };
+
+  // SRC holds an XZ-encoded "Hello Wuffs." with a 4 KiB dictionary.
+  //
+  // $ echo "Hello Wuffs." | xz --lzma2=dict=4KiB | xxd
+  const SRC: [u8; 76] = [
+    0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00, 0x00, 0x04, // 00..07
+    0xe6, 0xd6, 0xb4, 0x46, 0x03, 0xc0, 0x11, 0x0d, // 08..0F
+    0x21, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // 10..17
+    0x12, 0x5c, 0x6a, 0x07, 0x01, 0x00, 0x0c, 0x48, // 18..1F
+    0x65, 0x6c, 0x6c, 0x6f, 0x20, 0x57, 0x75, 0x66, // 20..27
+    0x66, 0x73, 0x2e, 0x0a, 0x00, 0x00, 0x00, 0x00, // 28..2F
+    0x32, 0xf5, 0x67, 0x8c, 0x61, 0x19, 0xbd, 0xd9, // 30..37
+    0x00, 0x01, 0x29, 0x0d, 0x7d, 0x56, 0x71, 0x1a, // 38..3F
+    0x1f, 0xb6, 0xf3, 0x7d, 0x01, 0x00, 0x00, 0x00, // 40..47
+    0x00, 0x04, 0x59, 0x5a, // 48..4B
+  ];
+
+  #[test]
+  fn test_xz_decode() {
+    let mut dst = [0; 1024];
+
+    let mut xz = super::WuffsXzDecoder::new().unwrap();
+    let (read, written) = xz.decode(&SRC, &mut dst).unwrap();
+
+    assert_eq!(read, SRC.len());
+    assert_eq!(&dst[..written], b"Hello Wuffs.\n");
+    assert!(xz.workbuf_len() > 0);
+  }
+
+  #[test]
+  fn test_xz_fork() {
+    let mut head = [0; 4];
+    let mut src = unsafe { WuffsBuf::from_slice_readonly(&SRC) };
+    let mut dst = WuffsBuf::from_slice(&mut head);
+
+    let mut xz = super::WuffsXzDecoder::new().unwrap();
+    let err = xz.transform_io(&mut dst, &mut src).unwrap_err();
+
+    assert_eq!(err, WuffsError::Suspension(WuffsSuspension::ShortWrite));
+    assert_eq!(&head, b"Hell");
+
+    let mut fork = xz.fork();
+    let rest = &SRC[src.read()..];
+
+    let mut tail_a = [0; 1024];
+    let mut tail_b = [0; 1024];
+    let (_, written_a) = xz.decode(rest, &mut tail_a).unwrap();
+    let (_, written_b) = fork.decode(rest, &mut tail_b).unwrap();
+
+    assert_eq!(&tail_a[..written_a], b"o Wuffs.\n");
+    assert_eq!(&tail_b[..written_b], b"o Wuffs.\n");
+  }
+
+  #[test]
+  fn test_xz_short_work() {
+    use crate::boxed::WuffsInline;
+
+    let mut dst = [0; 1024];
+    let mut work = [0; 16];
+
+    let mut xz =
+      super::WuffsXzDecoder::with_work(WuffsInline::new(), &mut work[..]).unwrap();
+    let err = xz.decode(&SRC, &mut dst).unwrap_err();
+
+    assert!(matches!(err, WuffsError::BufferTooSmall { len: 16, .. }));
+  }
+}
@@ -1,73 +1,65 @@
-use crate::{
-  boxed::{WuffsBox, WuffsBoxed},
-  buf::WuffsBuf,
-  slice::WuffsSlice,
-  status::{IntoResult, WuffsError},
-};
-use wuffs_sys::{
-  sizeof__wuffs_zlib__decoder, wuffs_zlib__decoder, wuffs_zlib__decoder__initialize,
-  wuffs_zlib__decoder__set_quirk_enabled, wuffs_zlib__decoder__transform_io,
-  WUFFS_VERSION, WUFFS_ZLIB__DECODER_WORKBUF_LEN_MAX_INCL_WORST_CASE as WORK_BUF_SIZE,
-};
-
-#[derive(Clone)]
-pub struct WuffsZlibDecoder {
-  work: Vec<u8>,
-  inner: WuffsBox<wuffs_zlib__decoder>,
+io_decoder! {
+  /// Decoder for zlib streams, as specified by RFC 1950.
+  WuffsZlibDecoder, wuffs_zlib__decoder {
+    sizeof: sizeof__wuffs_zlib__decoder,
+    initialize: wuffs_zlib__decoder__initialize,
+    set_quirk_enabled: wuffs_zlib__decoder__set_quirk_enabled,
+    set_quirk: wuffs_zlib__decoder__set_quirk,
+    transform_io: wuffs_zlib__decoder__transform_io,
+    workbuf_len: WUFFS_ZLIB__DECODER_WORKBUF_LEN_MAX_INCL_WORST_CASE,
+  }
 }
 
-impl WuffsZlibDecoder {
-  pub fn new() -> Result<Self, WuffsError> {
-    let mut inner = WuffsBox::new();
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  use crate::{
+    buf::WuffsBuf,
+    status::{WuffsError, WuffsSuspension},
+  };
 
-    unsafe {
-      wuffs_zlib__decoder__initialize(
-        inner.as_mut_ptr(),
-        inner.size() as _,
-        WUFFS_VERSION as _,
-        0x01,
-      )
-      .into_result()?;
-    }
+  // SRC holds a zlib-encoded "Hello Wuffs."
+  //
+  // $ echo "Hello Wuffs." | pigz --zlib -9 | xxd
+  // 00000000: 78da f348 cdc9 c957 082f 4d4b 2bd6 e302  x..H...W./MK+...
+  // 00000010: 0020 a804 58                             . ..X
+  const SRC: [u8; 21] = [
+    0x78, 0xda, 0xf3, 0x48, 0xcd, 0xc9, 0xc9, 0x57, // 00..07
+    0x08, 0x2f, 0x4d, 0x4b, 0x2b, 0xd6, 0xe3, 0x02, // 08..0F
+    0x00, 0x20, 0xa8, 0x04, 0x58, // 10..14
+  ];
 
-    Ok(Self {
-      work: vec![0; WORK_BUF_SIZE as _],
-      inner,
-    })
-  }
+  #[test]
+  fn test_zlib_decode() {
+    let mut dst = [0; 1024];
+
+    let mut zlib = super::WuffsZlibDecoder::new().unwrap();
+    let (read, written) = zlib.decode(&SRC, &mut dst).unwrap();
 
-  pub fn set_quirk_enabled(&mut self, quirk: u32, enabled: bool) {
-    unsafe {
-      wuffs_zlib__decoder__set_quirk_enabled(self.inner.as_mut_ptr(), quirk, enabled);
-    }
+    assert_eq!(read, SRC.len());
+    assert_eq!(&dst[..written], b"Hello Wuffs.\n");
   }
 
-  pub fn decode(
-    &mut self,
-    src: &[u8],
-    dst: &mut [u8],
-  ) -> Result<(usize, usize), WuffsError> {
-    let mut src = unsafe { WuffsBuf::from_slice_readonly(src) };
-    let mut dst = WuffsBuf::from_slice(dst);
+  #[test]
+  fn test_zlib_fork() {
+    let mut head = [0; 4];
+    let mut src = unsafe { WuffsBuf::from_slice_readonly(&SRC) };
+    let mut dst = WuffsBuf::from_slice(&mut head);
 
-    let work = WuffsSlice::from(&mut self.work[..]);
+    let mut zlib = super::WuffsZlibDecoder::new().unwrap();
+    let err = zlib.transform_io(&mut dst, &mut src).unwrap_err();
 
-    unsafe {
-      wuffs_zlib__decoder__transform_io(
-        self.inner.as_mut_ptr(),
-        dst.as_mut_ptr(),
-        src.as_mut_ptr(),
-        work.into_inner(),
-      )
-      .into_result()?
-    };
+    assert_eq!(err, WuffsError::Suspension(WuffsSuspension::ShortWrite));
+    assert_eq!(&head, b"Hell");
 
-    Ok((src.read(), dst.written()))
-  }
-}
+    let mut fork = zlib.fork();
+    let rest = &SRC[src.read()..];
+
+    let mut tail_a = [0; 1024];
+    let mut tail_b = [0; 1024];
+    let (_, written_a) = zlib.decode(rest, &mut tail_a).unwrap();
+    let (_, written_b) = fork.decode(rest, &mut tail_b).unwrap();
 
-impl WuffsBoxed for wuffs_zlib__decoder {
-  fn size() -> usize {
-    unsafe { sizeof__wuffs_zlib__decoder() as _ }
+    assert_eq!(&tail_a[..written_a], b"o Wuffs.\n");
+    assert_eq!(&tail_b[..written_b], b"o Wuffs.\n");
   }
 }
@@ -1,30 +1,58 @@
 use super::WuffsHash;
 use crate::{
-  boxed::{WuffsBox, WuffsBoxed},
+  boxed::{WuffsBoxed, WuffsDefault, WuffsSized, WuffsStorage},
   slice::WuffsSlice,
   status::{IntoResult, WuffsError},
 };
+use core::hash::Hasher;
+#[cfg(feature = "std")]
+use std::io::Write;
 use wuffs_sys::*;
 
+/// Adler-32 of zero bytes.
+const INITIAL: u32 = 1;
+
+/// Adler-32 hasher.
 #[derive(Clone)]
-pub struct WuffsAdler32(WuffsBox<wuffs_adler32__hasher>);
+pub struct WuffsAdler32<B = WuffsDefault<wuffs_adler32__hasher>> {
+  inner: B,
+  /// Checksum of the bytes that came before this hasher, see `with_initial`.
+  initial: u32,
+}
 
 impl WuffsAdler32 {
+  #[cfg(feature = "alloc")]
   pub fn new() -> Result<Self, WuffsError> {
-    unsafe {
-      let mut inner = WuffsBox::new();
+    Self::with_storage(WuffsDefault::default())
+  }
+
+  /// Resume hashing after bytes whose Adler-32 was `initial`.
+  #[cfg(feature = "alloc")]
+  pub fn with_initial(initial: u32) -> Result<Self, WuffsError> {
+    let mut hasher = Self::new()?;
+    hasher.initial = initial;
+    hasher.seed();
+    Ok(hasher)
+  }
+}
 
+impl<B: WuffsStorage<wuffs_adler32__hasher>> WuffsAdler32<B> {
+  /// Initialize a hasher whose state lives in `inner`.
+  pub fn with_storage(mut inner: B) -> Result<Self, WuffsError> {
+    unsafe {
       wuffs_adler32__hasher__initialize(
-        //
         inner.as_mut_ptr(),
         inner.size() as _,
         WUFFS_VERSION as _,
-        0x00000001, // WUFFS_INITIALIZE__ALREADY_ZEROED
+        0,
       )
       .into_result()?;
-
-      Ok(Self(inner))
     }
+
+    Ok(Self {
+      inner,
+      initial: INITIAL,
+    })
   }
 
   pub fn update<S>(&mut self, buf: S) -> u32
@@ -33,20 +61,169 @@ impl WuffsAdler32 {
   {
     unsafe {
       wuffs_adler32__hasher__update_u32(
-        self.0.as_mut_ptr(),
+        self.inner.as_mut_ptr(),
         WuffsSlice::<u8>::from_readonly(buf.as_ref()),
       )
     }
   }
+
+  /// Adler-32 of everything seen so far, including the `initial` value.
+  pub fn value(&self) -> u32 {
+    let state = unsafe { &(*self.inner.as_ptr()).private_impl };
+
+    if state.f_started {
+      state.f_state
+    } else {
+      INITIAL
+    }
+  }
+
+  pub fn finish(&self) -> u32 {
+    self.value()
+  }
+
+  /// Return to the state right after construction, keeping any `initial` value.
+  pub fn reset(&mut self) {
+    unsafe {
+      wuffs_adler32__hasher__initialize(
+        self.inner.as_mut_ptr(),
+        self.inner.size() as _,
+        WUFFS_VERSION as _,
+        0,
+      )
+      .into_result()
+      .expect("hasher was already initialized with the same arguments");
+    }
+
+    self.seed();
+  }
+
+  /// Carry on from `initial`.  The first update starts the hasher at 1 and picks its SIMD
+  /// routine, so an empty one goes first and `initial` then replaces that 1.
+  fn seed(&mut self) {
+    self.update(b"");
+    unsafe { (*self.inner.as_mut_ptr()).private_impl.f_state = self.initial };
+  }
 }
 
-impl WuffsHash for WuffsAdler32 {
+#[cfg(feature = "alloc")]
+impl Default for WuffsAdler32 {
+  fn default() -> Self {
+    Self::new().expect("failed to initialize adler32 hasher")
+  }
+}
+
+impl<B: WuffsStorage<wuffs_adler32__hasher>> WuffsHash for WuffsAdler32<B> {
   fn update<S>(&mut self, buf: S) -> u32
   where
     S: AsRef<[u8]>,
   {
     self.update(buf)
   }
+
+  fn finish(&self) -> u32 {
+    self.finish()
+  }
+
+  fn reset(&mut self) {
+    self.reset()
+  }
+}
+
+impl<B: WuffsStorage<wuffs_adler32__hasher>> Hasher for WuffsAdler32<B> {
+  fn finish(&self) -> u64 {
+    self.value() as _
+  }
+
+  fn write(&mut self, bytes: &[u8]) {
+    self.update(bytes);
+  }
+}
+
+#[cfg(feature = "std")]
+impl<B: WuffsStorage<wuffs_adler32__hasher>> Write for WuffsAdler32<B> {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    self.update(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    Ok(())
+  }
+}
+
+#[cfg(feature = "digest")]
+mod digest_impl {
+  use super::WuffsAdler32;
+  use crate::boxed::WuffsStorage;
+  use digest::{
+    consts::U4, FixedOutput, FixedOutputReset, HashMarker, Output, OutputSizeUser, Reset,
+    Update,
+  };
+  use wuffs_sys::wuffs_adler32__hasher;
+
+  impl<B: WuffsStorage<wuffs_adler32__hasher>> HashMarker for WuffsAdler32<B> {}
+
+  impl<B: WuffsStorage<wuffs_adler32__hasher>> OutputSizeUser for WuffsAdler32<B> {
+    type OutputSize = U4;
+  }
+
+  impl<B: WuffsStorage<wuffs_adler32__hasher>> Update for WuffsAdler32<B> {
+    fn update(&mut self, data: &[u8]) {
+      Self::update(self, data);
+    }
+  }
+
+  impl<B: WuffsStorage<wuffs_adler32__hasher>> FixedOutput for WuffsAdler32<B> {
+    fn finalize_into(self, out: &mut Output<Self>) {
+      out.copy_from_slice(&self.value().to_be_bytes());
+    }
+  }
+
+  impl<B: WuffsStorage<wuffs_adler32__hasher>> Reset for WuffsAdler32<B> {
+    fn reset(&mut self) {
+      Self::reset(self);
+    }
+  }
+
+  impl<B: WuffsStorage<wuffs_adler32__hasher>> FixedOutputReset for WuffsAdler32<B> {
+    fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+      out.copy_from_slice(&self.value().to_be_bytes());
+      Self::reset(self);
+    }
+  }
+}
+
+/// Largest prime smaller than 65536.
+const BASE: u64 = 65521;
+
+/// Combine `adler1`, the Adler-32 of some data `A`, with `adler2`, the Adler-32 of `len2`
+/// bytes of data `B`, into the Adler-32 of `A` followed by `B`.
+pub fn adler32_combine(adler1: u32, adler2: u32, len2: u64) -> u32 {
+  let rem = len2 % BASE;
+  let mut sum1 = (adler1 & 0xffff) as u64;
+  let mut sum2 = (rem * sum1) % BASE;
+
+  sum1 += (adler2 & 0xffff) as u64 + BASE - 1;
+  sum2 += (adler1 >> 16) as u64 + (adler2 >> 16) as u64 + BASE - rem;
+
+  if sum1 >= BASE {
+    sum1 -= BASE;
+  }
+
+  if sum1 >= BASE {
+    sum1 -= BASE;
+  }
+
+  if sum2 >= BASE << 1 {
+    sum2 -= BASE << 1;
+  }
+
+  if sum2 >= BASE {
+    sum2 -= BASE;
+  }
+
+  (sum1 | (sum2 << 16)) as u32
 }
 
 impl WuffsBoxed for wuffs_adler32__hasher {
@@ -55,8 +232,11 @@ impl WuffsBoxed for wuffs_adler32__hasher {
   }
 }
 
-#[cfg(test)]
+unsafe impl WuffsSized for wuffs_adler32__hasher {}
+
+#[cfg(all(test, feature = "alloc"))]
 mod tests {
+
   #[test]
   fn test_adler32() {
     let mut adler = super::WuffsAdler32::new().unwrap();
@@ -64,4 +244,80 @@ mod tests {
 
     assert_eq!(sum, 1921255656);
   }
+
+  #[test]
+  fn test_adler32_finish_reset() {
+    let mut adler = super::WuffsAdler32::new().unwrap();
+
+    assert_eq!(adler.finish(), 1);
+
+    adler.update(b"rust is pretty ");
+    adler.update(b"cool, man");
+
+    assert_eq!(adler.finish(), 1921255656);
+
+    adler.reset();
+
+    assert_eq!(adler.finish(), 1);
+    assert_eq!(adler.update(b"rust is pretty cool, man"), 1921255656);
+  }
+
+  #[test]
+  fn test_adler32_with_initial() {
+    let mut adler = super::WuffsAdler32::new().unwrap();
+    adler.update(b"rust is pretty ");
+
+    let mut resumed = super::WuffsAdler32::with_initial(adler.value()).unwrap();
+
+    assert_eq!(resumed.value(), adler.value());
+    assert_eq!(resumed.update(b"cool, man"), 1921255656);
+
+    resumed.reset();
+
+    assert_eq!(resumed.value(), adler.value());
+  }
+
+  #[test]
+  fn test_adler32_resume_mid_stream() {
+    let data = (0..100_000u32)
+      .map(|i| (i * 7919 % 251) as u8)
+      .collect::<Vec<_>>();
+    let sum = super::WuffsAdler32::new().unwrap().update(&data);
+
+    for &mid in &[1, 7, 4096, 65521, 99_999] {
+      let (a, b) = data.split_at(mid);
+      let adler_a = super::WuffsAdler32::new().unwrap().update(a);
+      let mut resumed = super::WuffsAdler32::with_initial(adler_a).unwrap();
+
+      assert_eq!(resumed.update(b), sum);
+    }
+  }
+
+  #[test]
+  fn test_adler32_combine() {
+    let data = (0..100_000u32)
+      .map(|i| (i * 7919 % 251) as u8)
+      .collect::<Vec<_>>();
+    let sum = super::WuffsAdler32::new().unwrap().update(&data);
+
+    for &mid in &[0, 1, 7, 4096, 65521, 99_999, 100_000] {
+      let (a, b) = data.split_at(mid);
+      let adler_a = super::WuffsAdler32::new().unwrap().update(a);
+      let adler_b = super::WuffsAdler32::new().unwrap().update(b);
+
+      assert_eq!(super::adler32_combine(adler_a, adler_b, b.len() as _), sum);
+    }
+  }
+
+  #[test]
+  #[cfg(feature = "std")]
+  fn test_adler32_hasher_write() {
+    use std::{hash::Hasher, io::Write};
+
+    let mut adler = super::WuffsAdler32::new().unwrap();
+    Hasher::write(&mut adler, b"rust is pretty ");
+    Write::write_all(&mut adler, b"cool, man").unwrap();
+
+    assert_eq!(Hasher::finish(&adler), 1921255656);
+  }
 }
@@ -1,30 +1,58 @@
 use super::WuffsHash;
 use crate::{
-  boxed::{WuffsBox, WuffsBoxed},
+  boxed::{WuffsBoxed, WuffsDefault, WuffsSized, WuffsStorage},
   slice::WuffsSlice,
   status::{IntoResult, WuffsError},
 };
+use core::hash::Hasher;
+#[cfg(feature = "std")]
+use std::io::Write;
 use wuffs_sys::*;
 
+/// CRC-32 of zero bytes.
+const INITIAL: u32 = 0;
+
+/// CRC-32 hasher.
 #[derive(Clone)]
-pub struct WuffsCrc32(WuffsBox<wuffs_crc32__ieee_hasher>);
+pub struct WuffsCrc32<B = WuffsDefault<wuffs_crc32__ieee_hasher>> {
+  inner: B,
+  /// Checksum of the bytes that came before this hasher, see `with_initial`.
+  initial: u32,
+}
 
 impl WuffsCrc32 {
+  #[cfg(feature = "alloc")]
   pub fn new() -> Result<Self, WuffsError> {
-    unsafe {
-      let mut inner = WuffsBox::new();
+    Self::with_storage(WuffsDefault::default())
+  }
+
+  /// Resume hashing after bytes whose CRC-32 was `initial`.
+  #[cfg(feature = "alloc")]
+  pub fn with_initial(initial: u32) -> Result<Self, WuffsError> {
+    let mut hasher = Self::new()?;
+    hasher.initial = initial;
+    hasher.seed();
+    Ok(hasher)
+  }
+}
 
+impl<B: WuffsStorage<wuffs_crc32__ieee_hasher>> WuffsCrc32<B> {
+  /// Initialize a hasher whose state lives in `inner`.
+  pub fn with_storage(mut inner: B) -> Result<Self, WuffsError> {
+    unsafe {
       wuffs_crc32__ieee_hasher__initialize(
-        //
         inner.as_mut_ptr(),
         inner.size() as _,
         WUFFS_VERSION as _,
-        0x00000001, // WUFFS_INITIALIZE__ALREADY_ZEROED
+        0,
       )
       .into_result()?;
-
-      Ok(Self(inner))
     }
+
+    Ok(Self {
+      inner,
+      initial: INITIAL,
+    })
   }
 
   pub fn update<S>(&mut self, buf: S) -> u32
@@ -33,20 +61,177 @@ impl WuffsCrc32 {
   {
     unsafe {
       wuffs_crc32__ieee_hasher__update_u32(
-        self.0.as_mut_ptr(),
+        self.inner.as_mut_ptr(),
         WuffsSlice::<u8>::from_readonly(buf.as_ref()),
       )
     }
   }
+
+  /// CRC-32 of everything seen so far, including the `initial` value.
+  pub fn value(&self) -> u32 {
+    unsafe { (*self.inner.as_ptr()).private_impl.f_state }
+  }
+
+  pub fn finish(&self) -> u32 {
+    self.value()
+  }
+
+  /// Return to the state right after construction, keeping any `initial` value.
+  pub fn reset(&mut self) {
+    unsafe {
+      wuffs_crc32__ieee_hasher__initialize(
+        self.inner.as_mut_ptr(),
+        self.inner.size() as _,
+        WUFFS_VERSION as _,
+        0,
+      )
+      .into_result()
+      .expect("hasher was already initialized with the same arguments");
+    }
+
+    self.seed();
+  }
+
+  /// Carry on from `initial`, which the hasher keeps as its running checksum.  wuffs only
+  /// picks its SIMD routine on an update while the state is still zero, so that empty
+  /// update has to come first.
+  fn seed(&mut self) {
+    self.update(b"");
+    unsafe { (*self.inner.as_mut_ptr()).private_impl.f_state = self.initial };
+  }
 }
 
-impl WuffsHash for WuffsCrc32 {
+#[cfg(feature = "alloc")]
+impl Default for WuffsCrc32 {
+  fn default() -> Self {
+    Self::new().expect("failed to initialize crc32 hasher")
+  }
+}
+
+impl<B: WuffsStorage<wuffs_crc32__ieee_hasher>> WuffsHash for WuffsCrc32<B> {
   fn update<S>(&mut self, buf: S) -> u32
   where
     S: AsRef<[u8]>,
   {
     self.update(buf)
   }
+
+  fn finish(&self) -> u32 {
+    self.finish()
+  }
+
+  fn reset(&mut self) {
+    self.reset()
+  }
+}
+
+impl<B: WuffsStorage<wuffs_crc32__ieee_hasher>> Hasher for WuffsCrc32<B> {
+  fn finish(&self) -> u64 {
+    self.value() as _
+  }
+
+  fn write(&mut self, bytes: &[u8]) {
+    self.update(bytes);
+  }
+}
+
+#[cfg(feature = "std")]
+impl<B: WuffsStorage<wuffs_crc32__ieee_hasher>> Write for WuffsCrc32<B> {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    self.update(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    Ok(())
+  }
+}
+
+#[cfg(feature = "digest")]
+mod digest_impl {
+  use super::WuffsCrc32;
+  use crate::boxed::WuffsStorage;
+  use digest::{
+    consts::U4, FixedOutput, FixedOutputReset, HashMarker, Output, OutputSizeUser, Reset,
+    Update,
+  };
+  use wuffs_sys::wuffs_crc32__ieee_hasher;
+
+  impl<B: WuffsStorage<wuffs_crc32__ieee_hasher>> HashMarker for WuffsCrc32<B> {}
+
+  impl<B: WuffsStorage<wuffs_crc32__ieee_hasher>> OutputSizeUser for WuffsCrc32<B> {
+    type OutputSize = U4;
+  }
+
+  impl<B: WuffsStorage<wuffs_crc32__ieee_hasher>> Update for WuffsCrc32<B> {
+    fn update(&mut self, data: &[u8]) {
+      Self::update(self, data);
+    }
+  }
+
+  impl<B: WuffsStorage<wuffs_crc32__ieee_hasher>> FixedOutput for WuffsCrc32<B> {
+    fn finalize_into(self, out: &mut Output<Self>) {
+      out.copy_from_slice(&self.value().to_be_bytes());
+    }
+  }
+
+  impl<B: WuffsStorage<wuffs_crc32__ieee_hasher>> Reset for WuffsCrc32<B> {
+    fn reset(&mut self) {
+      Self::reset(self);
+    }
+  }
+
+  impl<B: WuffsStorage<wuffs_crc32__ieee_hasher>> FixedOutputReset for WuffsCrc32<B> {
+    fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+      out.copy_from_slice(&self.value().to_be_bytes());
+      Self::reset(self);
+    }
+  }
+}
+
+/// Reversed CRC-32/IEEE polynomial.
+const POLY: u32 = 0xedb8_8320;
+
+/// Combine `crc1`, the CRC-32 of some data `A`, with `crc2`, the CRC-32 of `len2` bytes
+/// of data `B`, into the CRC-32 of `A` followed by `B`.
+pub fn crc32_combine(crc1: u32, crc2: u32, len2: u64) -> u32 {
+  // Multiply `crc1` by x^(8 * len2) modulo the polynomial, squaring x^8 for each bit of
+  // `len2`.  In the reflected representation x^k is the bit `31 - k`.
+  let mut shift = 1 << 31;
+  let mut square = 1 << 23;
+  let mut len2 = len2;
+
+  while len2 != 0 {
+    if len2 & 1 != 0 {
+      shift = multmodp(square, shift);
+    }
+
+    square = multmodp(square, square);
+    len2 >>= 1;
+  }
+
+  multmodp(shift, crc1) ^ crc2
+}
+
+/// Multiply `a` by `b` modulo the CRC-32 polynomial.  `a` must not be zero.
+fn multmodp(a: u32, mut b: u32) -> u32 {
+  let mut m = 1 << 31;
+  let mut p = 0;
+
+  loop {
+    if a & m != 0 {
+      p ^= b;
+
+      if a & (m - 1) == 0 {
+        break;
+      }
+    }
+
+    m >>= 1;
+    b = if b & 1 != 0 { (b >> 1) ^ POLY } else { b >> 1 };
+  }
+
+  p
 }
 
 impl WuffsBoxed for wuffs_crc32__ieee_hasher {
@@ -55,8 +240,12 @@ impl WuffsBoxed for wuffs_crc32__ieee_hasher {
   }
 }
 
-#[cfg(test)]
+unsafe impl WuffsSized for wuffs_crc32__ieee_hasher {}
+
+#[cfg(all(test, feature = "alloc"))]
 mod tests {
+  use crate::boxed::WuffsInline;
+
   #[test]
   fn test_crc32() {
     let mut adler = super::WuffsCrc32::new().unwrap();
@@ -64,4 +253,85 @@ mod tests {
 
     assert_eq!(sum, 0xb80184ca);
   }
+
+  #[test]
+  fn test_crc32_inline() {
+    let mut crc = super::WuffsCrc32::with_storage(WuffsInline::new()).unwrap();
+    let sum = crc.update(b"rust is pretty cool, man");
+
+    assert_eq!(sum, 0xb80184ca);
+  }
+
+  #[test]
+  fn test_crc32_finish_reset() {
+    let mut crc = super::WuffsCrc32::new().unwrap();
+    crc.update(b"rust is pretty ");
+    crc.update(b"cool, man");
+
+    assert_eq!(crc.finish(), 0xb80184ca);
+
+    crc.reset();
+
+    assert_eq!(crc.finish(), 0);
+    assert_eq!(crc.update(b"rust is pretty cool, man"), 0xb80184ca);
+  }
+
+  #[test]
+  fn test_crc32_with_initial() {
+    let mut crc = super::WuffsCrc32::new().unwrap();
+    crc.update(b"rust is pretty ");
+
+    let mut resumed = super::WuffsCrc32::with_initial(crc.value()).unwrap();
+
+    assert_eq!(resumed.value(), crc.value());
+    assert_eq!(resumed.update(b"cool, man"), 0xb80184ca);
+
+    resumed.reset();
+
+    assert_eq!(resumed.value(), crc.value());
+  }
+
+  #[test]
+  fn test_crc32_resume_mid_stream() {
+    let data = (0..100_000u32)
+      .map(|i| (i * 7919 % 251) as u8)
+      .collect::<Vec<_>>();
+    let sum = super::WuffsCrc32::new().unwrap().update(&data);
+
+    for &mid in &[1, 7, 4096, 65521, 99_999] {
+      let (a, b) = data.split_at(mid);
+      let crc_a = super::WuffsCrc32::new().unwrap().update(a);
+      let mut resumed = super::WuffsCrc32::with_initial(crc_a).unwrap();
+
+      assert_eq!(resumed.update(b), sum);
+    }
+  }
+
+  #[test]
+  fn test_crc32_combine() {
+    let data = (0..100_000u32)
+      .map(|i| (i * 7919 % 251) as u8)
+      .collect::<Vec<_>>();
+    let sum = super::WuffsCrc32::new().unwrap().update(&data);
+
+    for &mid in &[0, 1, 7, 4096, 65521, 99_999, 100_000] {
+      let (a, b) = data.split_at(mid);
+      let crc_a = super::WuffsCrc32::new().unwrap().update(a);
+      let crc_b = super::WuffsCrc32::new().unwrap().update(b);
+
+      assert_eq!(super::crc32_combine(crc_a, crc_b, b.len() as _), sum);
+    }
+  }
+
+  #[test]
+  #[cfg(feature = "std")]
+  fn test_crc32_hasher_write() {
+    use std::{hash::Hasher, io::Write};
+
+    let mut crc = super::WuffsCrc32::new().unwrap();
+    Hasher::write(&mut crc, b"rust is pretty ");
+    Write::write_all(&mut crc, b"cool, man").unwrap();
+
+    assert_eq!(Hasher::finish(&crc), 0xb80184ca);
+  }
 }
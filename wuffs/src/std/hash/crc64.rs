@@ -0,0 +1,126 @@
+use crate::{
+  boxed::{WuffsBoxed, WuffsDefault, WuffsSized, WuffsStorage},
+  slice::WuffsSlice,
+  status::{IntoResult, WuffsError},
+};
+use core::hash::Hasher;
+#[cfg(feature = "std")]
+use std::io::Write;
+use wuffs_sys::*;
+
+/// CRC-64/ECMA hasher, as used by XZ.
+#[derive(Clone)]
+pub struct WuffsCrc64<B = WuffsDefault<wuffs_crc64__ecma_hasher>> {
+  inner: B,
+  value: u64,
+}
+
+impl WuffsCrc64 {
+  #[cfg(feature = "alloc")]
+  pub fn new() -> Result<Self, WuffsError> {
+    Self::with_storage(WuffsDefault::default())
+  }
+}
+
+impl<B: WuffsStorage<wuffs_crc64__ecma_hasher>> WuffsCrc64<B> {
+  /// Initialize a hasher whose state lives in `inner`.
+  pub fn with_storage(mut inner: B) -> Result<Self, WuffsError> {
+    unsafe {
+      wuffs_crc64__ecma_hasher__initialize(
+        inner.as_mut_ptr(),
+        inner.size() as _,
+        WUFFS_VERSION as _,
+        0,
+      )
+      .into_result()?;
+    }
+
+    Ok(Self { inner, value: 0 })
+  }
+
+  pub fn update<S>(&mut self, buf: S) -> u64
+  where
+    S: AsRef<[u8]>,
+  {
+    self.value = unsafe {
+      wuffs_crc64__ecma_hasher__update_u64(
+        self.inner.as_mut_ptr(),
+        WuffsSlice::<u8>::from_readonly(buf.as_ref()),
+      )
+    };
+
+    self.value
+  }
+
+  /// CRC-64 of everything seen so far.
+  pub fn value(&self) -> u64 {
+    self.value
+  }
+
+  pub fn reset(&mut self) {
+    unsafe {
+      wuffs_crc64__ecma_hasher__initialize(
+        self.inner.as_mut_ptr(),
+        self.inner.size() as _,
+        WUFFS_VERSION as _,
+        0,
+      )
+      .into_result()
+      .expect("hasher was already initialized with the same arguments");
+    }
+
+    self.value = 0;
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl Default for WuffsCrc64 {
+  fn default() -> Self {
+    Self::new().expect("failed to initialize crc64 hasher")
+  }
+}
+
+impl<B: WuffsStorage<wuffs_crc64__ecma_hasher>> Hasher for WuffsCrc64<B> {
+  fn finish(&self) -> u64 {
+    self.value
+  }
+
+  fn write(&mut self, bytes: &[u8]) {
+    self.update(bytes);
+  }
+}
+
+#[cfg(feature = "std")]
+impl<B: WuffsStorage<wuffs_crc64__ecma_hasher>> Write for WuffsCrc64<B> {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    self.update(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    Ok(())
+  }
+}
+
+impl WuffsBoxed for wuffs_crc64__ecma_hasher {
+  fn size() -> usize {
+    unsafe { sizeof__wuffs_crc64__ecma_hasher() as _ }
+  }
+}
+
+unsafe impl WuffsSized for wuffs_crc64__ecma_hasher {}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  #[test]
+  fn test_crc64() {
+    let mut crc = super::WuffsCrc64::new().unwrap();
+    crc.update(b"1234");
+
+    assert_eq!(crc.update(b"56789"), 0x995dc9bbdf1939fa);
+
+    crc.reset();
+
+    assert_eq!(crc.value(), 0);
+  }
+}
@@ -0,0 +1,141 @@
+use super::WuffsHash;
+use std::io::{Read, Result, Write};
+
+/// Reader adapter that hashes every byte read through it.
+pub struct HashingReader<R, H: WuffsHash> {
+  inner: R,
+  hasher: H,
+}
+
+impl<R, H: WuffsHash> HashingReader<R, H> {
+  pub fn new(inner: R, hasher: H) -> Self {
+    Self { inner, hasher }
+  }
+
+  pub fn get_ref(&self) -> &R {
+    &self.inner
+  }
+
+  pub fn get_mut(&mut self) -> &mut R {
+    &mut self.inner
+  }
+
+  pub fn hasher(&self) -> &H {
+    &self.hasher
+  }
+
+  /// Checksum of every byte read so far.
+  pub fn checksum(&self) -> u32 {
+    self.hasher.finish()
+  }
+
+  /// Unwrap into the inner reader and the checksum of every byte read.
+  pub fn finish(self) -> (R, u32) {
+    let checksum = self.hasher.finish();
+    (self.inner, checksum)
+  }
+
+  pub fn into_parts(self) -> (R, H) {
+    (self.inner, self.hasher)
+  }
+}
+
+impl<R: Read, H: WuffsHash> Read for HashingReader<R, H> {
+  fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+    let read = self.inner.read(buf)?;
+    self.hasher.update(&buf[..read]);
+    Ok(read)
+  }
+}
+
+/// Writer adapter that hashes every byte accepted by the inner writer.
+pub struct HashingWriter<W, H: WuffsHash> {
+  inner: W,
+  hasher: H,
+}
+
+impl<W, H: WuffsHash> HashingWriter<W, H> {
+  pub fn new(inner: W, hasher: H) -> Self {
+    Self { inner, hasher }
+  }
+
+  pub fn get_ref(&self) -> &W {
+    &self.inner
+  }
+
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.inner
+  }
+
+  pub fn hasher(&self) -> &H {
+    &self.hasher
+  }
+
+  /// Checksum of every byte written so far.
+  pub fn checksum(&self) -> u32 {
+    self.hasher.finish()
+  }
+
+  /// Unwrap into the inner writer and the checksum of every byte written.
+  pub fn finish(self) -> (W, u32) {
+    let checksum = self.hasher.finish();
+    (self.inner, checksum)
+  }
+
+  pub fn into_parts(self) -> (W, H) {
+    (self.inner, self.hasher)
+  }
+}
+
+impl<W: Write, H: WuffsHash> Write for HashingWriter<W, H> {
+  fn write(&mut self, buf: &[u8]) -> Result<usize> {
+    let written = self.inner.write(buf)?;
+    self.hasher.update(&buf[..written]);
+    Ok(written)
+  }
+
+  fn flush(&mut self) -> Result<()> {
+    self.inner.flush()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  #[cfg(feature = "crc32")]
+  fn test_hashing_reader() {
+    use super::HashingReader;
+    use crate::std::hash::crc32::WuffsCrc32;
+    use std::io::Read;
+
+    let src = &b"rust is pretty cool, man"[..];
+    let mut reader = HashingReader::new(src, WuffsCrc32::new().unwrap());
+    let mut dst = Vec::new();
+
+    reader.read_to_end(&mut dst).unwrap();
+
+    let (_, sum) = reader.finish();
+
+    assert_eq!(dst, src);
+    assert_eq!(sum, 0xb80184ca);
+  }
+
+  #[test]
+  #[cfg(feature = "adler32")]
+  fn test_hashing_writer() {
+    use super::HashingWriter;
+    use crate::std::hash::adler32::WuffsAdler32;
+    use std::io::{copy, Write};
+
+    let mut src = &b"rust is pretty cool, man"[..];
+    let mut writer = HashingWriter::new(Vec::new(), WuffsAdler32::new().unwrap());
+
+    copy(&mut src, &mut writer).unwrap();
+    writer.flush().unwrap();
+
+    let (dst, sum) = writer.finish();
+
+    assert_eq!(dst, b"rust is pretty cool, man");
+    assert_eq!(sum, 1921255656);
+  }
+}
@@ -1,8 +1,50 @@
+//! Checksums and digests built on wuffs hashers.
+//!
+//! Each hasher keeps its state in [`WuffsDefault`](crate::boxed::WuffsDefault) storage
+//! unless other storage is supplied through its `with_storage`.  That default is heap
+//! allocated, so without the `alloc` feature there is no `new` and `with_storage` takes
+//! [`WuffsInline`](crate::boxed::WuffsInline) or
+//! [`WuffsBorrowed`](crate::boxed::WuffsBorrowed) storage instead.
+
+#[cfg(feature = "adler32")]
 pub mod adler32;
+#[cfg(feature = "crc32")]
 pub mod crc32;
+#[cfg(feature = "crc64")]
+pub mod crc64;
+#[cfg(feature = "std")]
+pub mod io;
+mod oneshot;
+#[cfg(all(feature = "rayon", any(feature = "adler32", feature = "crc32")))]
+pub mod parallel;
+#[cfg(feature = "sha256")]
+pub mod sha256;
+#[cfg(feature = "xxhash32")]
+pub mod xxhash32;
+#[cfg(feature = "xxhash64")]
+pub mod xxhash64;
+
+#[cfg(feature = "std")]
+pub use io::{HashingReader, HashingWriter};
+#[cfg(feature = "adler32")]
+pub use oneshot::adler32;
+#[cfg(feature = "crc32")]
+pub use oneshot::crc32;
+#[cfg(all(feature = "rayon", feature = "adler32"))]
+pub use parallel::adler32_parallel;
+#[cfg(all(feature = "rayon", feature = "crc32"))]
+pub use parallel::crc32_parallel;
 
+/// Running 32-bit checksum backed by a wuffs hasher.
 pub trait WuffsHash {
+  /// Feed `buf` into the hasher and return the checksum of everything seen so far.
   fn update<S>(&mut self, buf: S) -> u32
   where
     S: AsRef<[u8]>;
+
+  /// Checksum of everything seen since construction or the last [`reset`](Self::reset).
+  fn finish(&self) -> u32;
+
+  /// Return the hasher to its initial state.
+  fn reset(&mut self);
 }
@@ -0,0 +1,43 @@
+#[cfg(feature = "adler32")]
+use super::adler32::WuffsAdler32;
+#[cfg(feature = "crc32")]
+use super::crc32::WuffsCrc32;
+
+/// Compute the CRC-32 of `buf` with the hasher state on the stack.
+#[cfg(feature = "crc32")]
+pub fn crc32<S>(buf: S) -> u32
+where
+  S: AsRef<[u8]>,
+{
+  WuffsCrc32::with_storage(crate::boxed::WuffsInline::new())
+    .expect("failed to initialize crc32 hasher")
+    .update(buf)
+}
+
+/// Compute the Adler-32 of `buf` with the hasher state on the stack.
+#[cfg(feature = "adler32")]
+pub fn adler32<S>(buf: S) -> u32
+where
+  S: AsRef<[u8]>,
+{
+  WuffsAdler32::with_storage(crate::boxed::WuffsInline::new())
+    .expect("failed to initialize adler32 hasher")
+    .update(buf)
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  #[cfg(feature = "crc32")]
+  fn test_crc32() {
+    assert_eq!(super::crc32(b"rust is pretty cool, man"), 0xb80184ca);
+    assert_eq!(super::crc32(b""), 0);
+  }
+
+  #[test]
+  #[cfg(feature = "adler32")]
+  fn test_adler32() {
+    assert_eq!(super::adler32(b"rust is pretty cool, man"), 1921255656);
+    assert_eq!(super::adler32(b""), 1);
+  }
+}
@@ -0,0 +1,80 @@
+#[cfg(feature = "adler32")]
+use super::adler32::{adler32_combine, WuffsAdler32};
+#[cfg(feature = "crc32")]
+use super::crc32::{crc32_combine, WuffsCrc32};
+use rayon::prelude::*;
+
+/// Bytes hashed per task.  Large enough that the combine step is negligible next to the
+/// SIMD hashing of each chunk.
+const CHUNK_LEN: usize = 1 << 20;
+
+/// Compute the CRC-32 of `buf`, hashing chunks on the rayon thread pool.
+#[cfg(feature = "crc32")]
+pub fn crc32_parallel(buf: &[u8]) -> u32 {
+  if buf.len() <= CHUNK_LEN {
+    return WuffsCrc32::default().update(buf);
+  }
+
+  let (crc, _) = buf
+    .par_chunks(CHUNK_LEN)
+    .map(|chunk| (WuffsCrc32::default().update(chunk), chunk.len() as u64))
+    .reduce(
+      || (0, 0),
+      |(crc1, len1), (crc2, len2)| (crc32_combine(crc1, crc2, len2), len1 + len2),
+    );
+
+  crc
+}
+
+/// Compute the Adler-32 of `buf`, hashing chunks on the rayon thread pool.
+#[cfg(feature = "adler32")]
+pub fn adler32_parallel(buf: &[u8]) -> u32 {
+  if buf.len() <= CHUNK_LEN {
+    return WuffsAdler32::default().update(buf);
+  }
+
+  let (adler, _) = buf
+    .par_chunks(CHUNK_LEN)
+    .map(|chunk| (WuffsAdler32::default().update(chunk), chunk.len() as u64))
+    .reduce(
+      || (1, 0),
+      |(adler1, len1), (adler2, len2)| {
+        (adler32_combine(adler1, adler2, len2), len1 + len2)
+      },
+    );
+
+  adler
+}
+
+#[cfg(test)]
+mod tests {
+  #[cfg(feature = "adler32")]
+  use crate::std::hash::adler32::WuffsAdler32;
+  #[cfg(feature = "crc32")]
+  use crate::std::hash::crc32::WuffsCrc32;
+
+  #[allow(dead_code)]
+  fn data() -> Vec<u8> {
+    (0..(super::CHUNK_LEN * 3 + 12345) as u32)
+      .map(|i| (i * 7919 % 251) as u8)
+      .collect()
+  }
+
+  #[test]
+  #[cfg(feature = "crc32")]
+  fn test_crc32_parallel() {
+    let data = data();
+    let sum = WuffsCrc32::new().unwrap().update(&data);
+
+    assert_eq!(super::crc32_parallel(&data), sum);
+  }
+
+  #[test]
+  #[cfg(feature = "adler32")]
+  fn test_adler32_parallel() {
+    let data = data();
+    let sum = WuffsAdler32::new().unwrap().update(&data);
+
+    assert_eq!(super::adler32_parallel(&data), sum);
+  }
+}
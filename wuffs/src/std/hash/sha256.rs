@@ -0,0 +1,129 @@
+use crate::{
+  boxed::{WuffsBoxed, WuffsDefault, WuffsSized, WuffsStorage},
+  slice::WuffsSlice,
+  status::{IntoResult, WuffsError},
+};
+#[cfg(feature = "std")]
+use std::io::Write;
+use wuffs_sys::*;
+
+/// SHA-256 hasher, as used by XZ.
+#[derive(Clone)]
+pub struct WuffsSha256<B = WuffsDefault<wuffs_sha256__hasher>> {
+  inner: B,
+}
+
+impl WuffsSha256 {
+  #[cfg(feature = "alloc")]
+  pub fn new() -> Result<Self, WuffsError> {
+    Self::with_storage(WuffsDefault::default())
+  }
+}
+
+impl<B: WuffsStorage<wuffs_sha256__hasher>> WuffsSha256<B> {
+  /// Initialize a hasher whose state lives in `inner`.
+  pub fn with_storage(mut inner: B) -> Result<Self, WuffsError> {
+    unsafe {
+      wuffs_sha256__hasher__initialize(
+        inner.as_mut_ptr(),
+        inner.size() as _,
+        WUFFS_VERSION as _,
+        0,
+      )
+      .into_result()?;
+    }
+
+    Ok(Self { inner })
+  }
+
+  pub fn update<S>(&mut self, buf: S)
+  where
+    S: AsRef<[u8]>,
+  {
+    unsafe {
+      wuffs_sha256__hasher__update(
+        self.inner.as_mut_ptr(),
+        WuffsSlice::<u8>::from_readonly(buf.as_ref()),
+      );
+    }
+  }
+
+  /// Digest of everything seen so far.  The hasher can carry on afterwards.
+  pub fn finish(&self) -> [u8; 32] {
+    // wuffs puts the first bytes of the digest in the last element.
+    let elements = unsafe {
+      wuffs_sha256__hasher__checksum_bitvec256(self.inner.as_ptr()).elements_u64
+    };
+    let mut digest = [0; 32];
+
+    for (chunk, element) in digest.chunks_exact_mut(8).zip(elements.iter().rev()) {
+      chunk.copy_from_slice(&element.to_be_bytes());
+    }
+
+    digest
+  }
+
+  pub fn reset(&mut self) {
+    unsafe {
+      wuffs_sha256__hasher__initialize(
+        self.inner.as_mut_ptr(),
+        self.inner.size() as _,
+        WUFFS_VERSION as _,
+        0,
+      )
+      .into_result()
+      .expect("hasher was already initialized with the same arguments");
+    }
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl Default for WuffsSha256 {
+  fn default() -> Self {
+    Self::new().expect("failed to initialize sha256 hasher")
+  }
+}
+
+#[cfg(feature = "std")]
+impl<B: WuffsStorage<wuffs_sha256__hasher>> Write for WuffsSha256<B> {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    self.update(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    Ok(())
+  }
+}
+
+impl WuffsBoxed for wuffs_sha256__hasher {
+  fn size() -> usize {
+    unsafe { sizeof__wuffs_sha256__hasher() as _ }
+  }
+}
+
+unsafe impl WuffsSized for wuffs_sha256__hasher {}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  #[test]
+  fn test_sha256() {
+    let mut sha = super::WuffsSha256::new().unwrap();
+    sha.update(b"a");
+    sha.update(b"bc");
+
+    assert_eq!(
+      sha.finish(),
+      [
+        0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d,
+        0xae, 0x22, 0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10,
+        0xff, 0x61, 0xf2, 0x00, 0x15, 0xad,
+      ]
+    );
+
+    sha.reset();
+    sha.update(b"abc");
+
+    assert_eq!(sha.finish()[..4], [0xba, 0x78, 0x16, 0xbf]);
+  }
+}
@@ -0,0 +1,146 @@
+use super::WuffsHash;
+use crate::{
+  boxed::{WuffsBoxed, WuffsDefault, WuffsSized, WuffsStorage},
+  slice::WuffsSlice,
+  status::{IntoResult, WuffsError},
+};
+use core::hash::Hasher;
+#[cfg(feature = "std")]
+use std::io::Write;
+use wuffs_sys::*;
+
+/// XXH32 of zero bytes.
+const INITIAL: u32 = 0x02cc5d05;
+
+/// XXH32 hasher with a zero seed.
+#[derive(Clone)]
+pub struct WuffsXxhash32<B = WuffsDefault<wuffs_xxhash32__hasher>> {
+  inner: B,
+  value: u32,
+}
+
+impl WuffsXxhash32 {
+  #[cfg(feature = "alloc")]
+  pub fn new() -> Result<Self, WuffsError> {
+    Self::with_storage(WuffsDefault::default())
+  }
+}
+
+impl<B: WuffsStorage<wuffs_xxhash32__hasher>> WuffsXxhash32<B> {
+  /// Initialize a hasher whose state lives in `inner`.
+  pub fn with_storage(mut inner: B) -> Result<Self, WuffsError> {
+    unsafe {
+      wuffs_xxhash32__hasher__initialize(
+        inner.as_mut_ptr(),
+        inner.size() as _,
+        WUFFS_VERSION as _,
+        0,
+      )
+      .into_result()?;
+    }
+
+    Ok(Self {
+      inner,
+      value: INITIAL,
+    })
+  }
+
+  pub fn update<S>(&mut self, buf: S) -> u32
+  where
+    S: AsRef<[u8]>,
+  {
+    self.value = unsafe {
+      wuffs_xxhash32__hasher__update_u32(
+        self.inner.as_mut_ptr(),
+        WuffsSlice::<u8>::from_readonly(buf.as_ref()),
+      )
+    };
+
+    self.value
+  }
+
+  /// XXH32 of everything seen so far.
+  pub fn value(&self) -> u32 {
+    self.value
+  }
+
+  pub fn reset(&mut self) {
+    unsafe {
+      wuffs_xxhash32__hasher__initialize(
+        self.inner.as_mut_ptr(),
+        self.inner.size() as _,
+        WUFFS_VERSION as _,
+        0,
+      )
+      .into_result()
+      .expect("hasher was already initialized with the same arguments");
+    }
+
+    self.value = INITIAL;
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl Default for WuffsXxhash32 {
+  fn default() -> Self {
+    Self::new().expect("failed to initialize xxhash32 hasher")
+  }
+}
+
+impl<B: WuffsStorage<wuffs_xxhash32__hasher>> WuffsHash for WuffsXxhash32<B> {
+  fn update<S>(&mut self, buf: S) -> u32
+  where
+    S: AsRef<[u8]>,
+  {
+    self.update(buf)
+  }
+
+  fn finish(&self) -> u32 {
+    self.value
+  }
+
+  fn reset(&mut self) {
+    self.reset()
+  }
+}
+
+impl<B: WuffsStorage<wuffs_xxhash32__hasher>> Hasher for WuffsXxhash32<B> {
+  fn finish(&self) -> u64 {
+    self.value as _
+  }
+
+  fn write(&mut self, bytes: &[u8]) {
+    self.update(bytes);
+  }
+}
+
+#[cfg(feature = "std")]
+impl<B: WuffsStorage<wuffs_xxhash32__hasher>> Write for WuffsXxhash32<B> {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    self.update(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    Ok(())
+  }
+}
+
+impl WuffsBoxed for wuffs_xxhash32__hasher {
+  fn size() -> usize {
+    unsafe { sizeof__wuffs_xxhash32__hasher() as _ }
+  }
+}
+
+unsafe impl WuffsSized for wuffs_xxhash32__hasher {}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  #[test]
+  fn test_xxhash32() {
+    let mut xxh = super::WuffsXxhash32::new().unwrap();
+
+    assert_eq!(xxh.update(b""), 0x02cc5d05);
+    assert_eq!(xxh.update(b"abc"), 0x32d153ff);
+  }
+}
@@ -0,0 +1,128 @@
+use crate::{
+  boxed::{WuffsBoxed, WuffsDefault, WuffsSized, WuffsStorage},
+  slice::WuffsSlice,
+  status::{IntoResult, WuffsError},
+};
+use core::hash::Hasher;
+#[cfg(feature = "std")]
+use std::io::Write;
+use wuffs_sys::*;
+
+/// XXH64 of zero bytes.
+const INITIAL: u64 = 0xef46db3751d8e999;
+
+/// XXH64 hasher with a zero seed.
+#[derive(Clone)]
+pub struct WuffsXxhash64<B = WuffsDefault<wuffs_xxhash64__hasher>> {
+  inner: B,
+  value: u64,
+}
+
+impl WuffsXxhash64 {
+  #[cfg(feature = "alloc")]
+  pub fn new() -> Result<Self, WuffsError> {
+    Self::with_storage(WuffsDefault::default())
+  }
+}
+
+impl<B: WuffsStorage<wuffs_xxhash64__hasher>> WuffsXxhash64<B> {
+  /// Initialize a hasher whose state lives in `inner`.
+  pub fn with_storage(mut inner: B) -> Result<Self, WuffsError> {
+    unsafe {
+      wuffs_xxhash64__hasher__initialize(
+        inner.as_mut_ptr(),
+        inner.size() as _,
+        WUFFS_VERSION as _,
+        0,
+      )
+      .into_result()?;
+    }
+
+    Ok(Self {
+      inner,
+      value: INITIAL,
+    })
+  }
+
+  pub fn update<S>(&mut self, buf: S) -> u64
+  where
+    S: AsRef<[u8]>,
+  {
+    self.value = unsafe {
+      wuffs_xxhash64__hasher__update_u64(
+        self.inner.as_mut_ptr(),
+        WuffsSlice::<u8>::from_readonly(buf.as_ref()),
+      )
+    };
+
+    self.value
+  }
+
+  /// XXH64 of everything seen so far.
+  pub fn value(&self) -> u64 {
+    self.value
+  }
+
+  pub fn reset(&mut self) {
+    unsafe {
+      wuffs_xxhash64__hasher__initialize(
+        self.inner.as_mut_ptr(),
+        self.inner.size() as _,
+        WUFFS_VERSION as _,
+        0,
+      )
+      .into_result()
+      .expect("hasher was already initialized with the same arguments");
+    }
+
+    self.value = INITIAL;
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl Default for WuffsXxhash64 {
+  fn default() -> Self {
+    Self::new().expect("failed to initialize xxhash64 hasher")
+  }
+}
+
+impl<B: WuffsStorage<wuffs_xxhash64__hasher>> Hasher for WuffsXxhash64<B> {
+  fn finish(&self) -> u64 {
+    self.value
+  }
+
+  fn write(&mut self, bytes: &[u8]) {
+    self.update(bytes);
+  }
+}
+
+#[cfg(feature = "std")]
+impl<B: WuffsStorage<wuffs_xxhash64__hasher>> Write for WuffsXxhash64<B> {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    self.update(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    Ok(())
+  }
+}
+
+impl WuffsBoxed for wuffs_xxhash64__hasher {
+  fn size() -> usize {
+    unsafe { sizeof__wuffs_xxhash64__hasher() as _ }
+  }
+}
+
+unsafe impl WuffsSized for wuffs_xxhash64__hasher {}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+  #[test]
+  fn test_xxhash64() {
+    let mut xxh = super::WuffsXxhash64::new().unwrap();
+
+    assert_eq!(xxh.update(b""), 0xef46db3751d8e999);
+    assert_eq!(xxh.update(b"abc"), 0x44bc2cf5ad770999);
+  }
+}
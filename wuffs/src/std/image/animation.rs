@@ -0,0 +1,347 @@
+use alloc::vec::Vec;
+use core::{marker::PhantomData, time::Duration};
+
+use super::{
+  WuffsDisposal, WuffsFrameConfig, WuffsImageConfig, WuffsImageDecoder, WuffsLimits,
+  WuffsMetadata, WuffsRect,
+};
+use crate::{
+  buf::WuffsBuf,
+  pixel::{WuffsPixelBuffer, WuffsPixelConfig, WuffsPixelFormat},
+  status::{WuffsError, WuffsNote},
+  swizzler::WuffsPixelBlend,
+};
+
+/// One fully composited frame of an animation.
+#[derive(Debug, Clone)]
+pub struct WuffsFrame {
+  /// The whole canvas as non-premultiplied RGBA, rows without padding.
+  pub pixels: Vec<u8>,
+  pub width: u32,
+  pub height: u32,
+  /// Zero-based index of the frame.
+  pub index: u64,
+  /// Area of the canvas the frame drew to.
+  pub bounds: WuffsRect,
+  /// How long the frame is shown.
+  pub duration: Duration,
+  /// What happens to `bounds` before the next frame is drawn.
+  pub disposal: WuffsDisposal,
+}
+
+/// Iterator over the frames of an image held in memory, yielding each as the whole
+/// canvas with every earlier frame composited underneath.
+///
+/// Frames are decoded straight onto the canvas, blending or overwriting as each frame
+/// config says.  Before the next frame, the area of the previous one is left alone,
+/// cleared to the background color or restored to what it was before, following its
+/// disposal.
+///
+/// Metadata is collected along the way, see [`metadata`](Self::metadata).
+pub struct WuffsFrames<'a, D> {
+  decoder: D,
+  src: WuffsBuf,
+  image: WuffsImageConfig,
+  metadata: WuffsMetadata,
+  canvas: Canvas,
+  done: bool,
+  _src: PhantomData<&'a [u8]>,
+}
+
+impl<'a, D: WuffsImageDecoder> WuffsFrames<'a, D> {
+  /// Decode the image config of `src` with a freshly initialized `decoder`, whose
+  /// [limits](WuffsImageDecoder::set_limits) also bound the canvas.
+  pub fn new(mut decoder: D, src: &'a [u8]) -> Result<Self, WuffsError> {
+    let mut src = unsafe { WuffsBuf::from_slice_readonly(src) };
+    let mut metadata = WuffsMetadata::default();
+    WuffsMetadata::enable(&mut decoder);
+    let image = metadata.decode_image_config(&mut decoder, &mut src)?;
+
+    Self::with_config(decoder, src, image, metadata)
+  }
+
+  /// Carry on from a `decoder` that has decoded `image` from `src`, which must borrow
+  /// from `'a`.
+  pub(crate) fn with_config(
+    decoder: D,
+    src: WuffsBuf,
+    image: WuffsImageConfig,
+    metadata: WuffsMetadata,
+  ) -> Result<Self, WuffsError> {
+    Ok(Self {
+      canvas: Canvas::new(&decoder, &image)?,
+      decoder,
+      src,
+      image,
+      metadata,
+      done: false,
+      _src: PhantomData,
+    })
+  }
+
+  pub fn image_config(&self) -> WuffsImageConfig {
+    self.image
+  }
+
+  /// Metadata reported so far: everything before the first frame once constructed,
+  /// and the rest once iteration has finished.
+  pub fn metadata(&self) -> &WuffsMetadata {
+    &self.metadata
+  }
+
+  /// Times the animation should be played, 0 meaning forever.  GIF may only state it
+  /// after the first frame, so this is most reliable once iteration has begun.
+  pub fn loop_count(&self) -> u32 {
+    self.decoder.num_animation_loops()
+  }
+
+  pub fn decoder(&self) -> &D {
+    &self.decoder
+  }
+
+  pub fn into_decoder(self) -> D {
+    self.decoder
+  }
+
+  fn decode_next(&mut self) -> Result<Option<WuffsFrame>, WuffsError> {
+    let frame = match self
+      .metadata
+      .decode_frame_config(&mut self.decoder, &mut self.src)
+    {
+      Ok(frame) => frame,
+      Err(WuffsError::Note(WuffsNote::EndOfData)) => return Ok(None),
+      Err(err) => return Err(err),
+    };
+
+    let (blend, _) = self.canvas.begin(&frame)?;
+    self
+      .decoder
+      .decode_frame(self.canvas.pixbuf_mut(), &mut self.src, blend)?;
+    self.canvas.finish(frame);
+
+    Ok(Some(self.canvas.frame(&frame)))
+  }
+}
+
+impl<D: WuffsImageDecoder> Iterator for WuffsFrames<'_, D> {
+  type Item = Result<WuffsFrame, WuffsError>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.done {
+      return None;
+    }
+
+    let frame = self.decode_next().transpose();
+    self.done = !matches!(frame, Some(Ok(_)));
+
+    frame
+  }
+}
+
+/// Non-premultiplied RGBA canvas that frames are composited onto, applying the
+/// disposal of each frame before the next one is drawn.
+pub(crate) struct Canvas {
+  pixbuf: WuffsPixelBuffer<'static>,
+  width: u32,
+  height: u32,
+  /// Previous frame, whose disposal is applied before the next one is drawn.
+  previous: Option<WuffsFrameConfig>,
+  /// Canvas from before the previous frame, when its disposal restores it.
+  restore: Option<Vec<u8>>,
+  /// Limits of the decoder, which the canvas and its restore copy count against.
+  limits: WuffsLimits,
+}
+
+impl Canvas {
+  /// Allocate a canvas for `image`, within the limits of `decoder`.  Drawing a frame
+  /// that restores the canvas after it takes a second copy, which is checked when the
+  /// frame begins.
+  pub(crate) fn new<D: WuffsImageDecoder>(
+    decoder: &D,
+    image: &WuffsImageConfig,
+  ) -> Result<Self, WuffsError> {
+    let config = WuffsPixelConfig::new(
+      WuffsPixelFormat::RgbaNonpremul,
+      image.width(),
+      image.height(),
+    );
+    // The canvas may take more bytes per pixel than the image's own format.
+    decoder.limits().check(&config)?;
+
+    Ok(Self {
+      pixbuf: WuffsPixelBuffer::new(config)?,
+      width: image.width(),
+      height: image.height(),
+      previous: None,
+      restore: None,
+      limits: decoder.limits(),
+    })
+  }
+
+  pub(crate) fn pixbuf(&self) -> &WuffsPixelBuffer<'static> {
+    &self.pixbuf
+  }
+
+  pub(crate) fn pixbuf_mut(&mut self) -> &mut WuffsPixelBuffer<'static> {
+    &mut self.pixbuf
+  }
+
+  /// Get ready to draw `frame`, returning the blend to decode it with and the area of
+  /// the canvas changed on the way.  Fails with [`WuffsError::LimitExceeded`] when the
+  /// canvas and the copy that `frame` restores afterwards are over the byte limit.
+  pub(crate) fn begin(
+    &mut self,
+    frame: &WuffsFrameConfig,
+  ) -> Result<(WuffsPixelBlend, WuffsRect), WuffsError> {
+    if frame.disposal() == WuffsDisposal::RestorePrevious {
+      let len = self.width as u64 * self.height as u64 * 4;
+      self.limits.check_bytes(len.saturating_mul(2))?;
+    }
+
+    let mut dirty = self.dispose_previous();
+
+    if frame.index() == 0 {
+      let full = WuffsRect {
+        min_x: 0,
+        min_y: 0,
+        max_x: self.width,
+        max_y: self.height,
+      };
+      self.fill(full, frame.background_color());
+      dirty = full;
+    }
+
+    if frame.disposal() == WuffsDisposal::RestorePrevious {
+      self.restore = Some(self.snapshot());
+    }
+
+    let blend = if frame.overwrite_instead_of_blend() {
+      WuffsPixelBlend::Src
+    } else {
+      WuffsPixelBlend::SrcOver
+    };
+
+    Ok((blend, dirty))
+  }
+
+  /// Record that `frame` has been drawn.
+  pub(crate) fn finish(&mut self, frame: WuffsFrameConfig) {
+    self.previous = Some(frame);
+  }
+
+  /// Forget the frames drawn so far, before drawing one that doesn't depend on them.
+  #[cfg(feature = "std")]
+  pub(crate) fn reset(&mut self) {
+    self.previous = None;
+    self.restore = None;
+  }
+
+  /// The canvas as it stands after drawing `frame`.
+  pub(crate) fn frame(&self, frame: &WuffsFrameConfig) -> WuffsFrame {
+    WuffsFrame {
+      pixels: self.snapshot(),
+      width: self.width,
+      height: self.height,
+      index: frame.index(),
+      bounds: frame.bounds().clip(self.width, self.height),
+      duration: frame.duration(),
+      disposal: frame.disposal(),
+    }
+  }
+
+  /// Copy of the whole canvas, rows without padding.
+  pub(crate) fn snapshot(&self) -> Vec<u8> {
+    self.pixbuf.rows().flatten().copied().collect()
+  }
+
+  fn dispose_previous(&mut self) -> WuffsRect {
+    let previous = match self.previous.take() {
+      Some(previous) => previous,
+      None => return WuffsRect::default(),
+    };
+    let bounds = previous.bounds().clip(self.width, self.height);
+
+    match previous.disposal() {
+      WuffsDisposal::None => return WuffsRect::default(),
+      WuffsDisposal::RestoreBackground => self.fill(bounds, previous.background_color()),
+      WuffsDisposal::RestorePrevious => {
+        if let Some(restore) = self.restore.take() {
+          let row_len = (self.width as usize * 4).max(1);
+
+          for (row, saved) in self.pixbuf.rows_mut().zip(restore.chunks(row_len)) {
+            row.copy_from_slice(saved);
+          }
+        }
+      }
+    }
+
+    bounds
+  }
+
+  /// Fill `rect` of the canvas with a premultiplied ARGB `color`.
+  fn fill(&mut self, rect: WuffsRect, color: u32) {
+    let rect = rect.clip(self.width, self.height);
+    let rgba = unpremultiply(color);
+
+    for row in self
+      .pixbuf
+      .rows_mut()
+      .skip(rect.min_y as _)
+      .take(rect.height() as _)
+    {
+      for pixel in row[rect.min_x as usize * 4..rect.max_x as usize * 4].chunks_mut(4) {
+        pixel.copy_from_slice(&rgba);
+      }
+    }
+  }
+}
+
+/// Convert premultiplied ARGB, blue in the low byte, to non-premultiplied RGBA bytes.
+fn unpremultiply(argb: u32) -> [u8; 4] {
+  let [b, g, r, a] = argb.to_le_bytes();
+
+  match a {
+    0 => [0; 4],
+    0xff => [r, g, b, a],
+    _ => {
+      let channel = |c: u8| ((c as u32 * 0xff + a as u32 / 2) / a as u32).min(0xff) as u8;
+      [channel(r), channel(g), channel(b), a]
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  #[cfg(feature = "gif")]
+  fn test_gif_frames() {
+    use super::WuffsFrames;
+    use crate::std::image::{gif::tests::ANIMATED, gif::WuffsGifDecoder, WuffsDisposal};
+
+    const RED: [u8; 4] = [0xff, 0, 0, 0xff];
+    const GREEN: [u8; 4] = [0, 0xff, 0, 0xff];
+
+    let mut frames =
+      WuffsFrames::new(WuffsGifDecoder::new().unwrap(), &ANIMATED).unwrap();
+
+    let first = frames.next().unwrap().unwrap();
+    assert_eq!(first.pixels, [RED, RED, RED, RED].concat());
+    assert_eq!(first.duration.as_millis(), 100);
+    assert_eq!(frames.loop_count(), 0);
+
+    let second = frames.next().unwrap().unwrap();
+    assert_eq!(second.pixels, [RED, RED, RED, GREEN].concat());
+    assert_eq!(second.index, 1);
+    assert_eq!(second.bounds.min_x, 1);
+    assert_eq!(second.disposal, WuffsDisposal::RestoreBackground);
+
+    assert!(frames.next().is_none());
+  }
+
+  #[test]
+  fn test_unpremultiply() {
+    assert_eq!(super::unpremultiply(0xff10_2030), [0x10, 0x20, 0x30, 0xff]);
+    assert_eq!(super::unpremultiply(0x8040_2010), [0x80, 0x40, 0x20, 0x80]);
+    assert_eq!(super::unpremultiply(0), [0; 4]);
+  }
+}
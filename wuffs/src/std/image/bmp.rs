@@ -0,0 +1,139 @@
+use super::WuffsLimits;
+use crate::{
+  boxed::{check_len, WuffsBorrowed, WuffsDefault, WuffsStorage},
+  status::{IntoResult, WuffsError},
+};
+use wuffs_sys::{
+  wuffs_bmp__decoder, wuffs_bmp__decoder__initialize,
+  WUFFS_BMP__DECODER_WORKBUF_LEN_MAX_INCL_WORST_CASE as WORK_BUF_SIZE, WUFFS_VERSION,
+};
+
+/// BMP decoder, for uncompressed and RLE encoded bitmaps.
+pub struct WuffsBmpDecoder<
+  B = WuffsDefault<wuffs_bmp__decoder>,
+  W = [u8; WORK_BUF_SIZE as usize],
+> {
+  work: W,
+  inner: B,
+  limits: WuffsLimits,
+}
+
+impl WuffsBmpDecoder {
+  /// Bytes of work buffer the decoder needs, see [`with_work`](Self::with_work).
+  pub const WORK_BUF_LEN: usize = WORK_BUF_SIZE as usize;
+
+  #[cfg(feature = "alloc")]
+  pub fn new() -> Result<Self, WuffsError> {
+    Self::with_storage(WuffsDefault::default())
+  }
+}
+
+impl<B: WuffsStorage<wuffs_bmp__decoder>> WuffsBmpDecoder<B> {
+  /// Initialize a decoder whose state lives in `inner`.
+  pub fn with_storage(inner: B) -> Result<Self, WuffsError> {
+    Self::with_work(inner, [0; WORK_BUF_SIZE as usize])
+  }
+}
+
+impl<'a> WuffsBmpDecoder<WuffsBorrowed<'a, wuffs_bmp__decoder>, &'a mut [u8]> {
+  /// Initialize a decoder without allocating: the state lives in `state`, which must
+  /// satisfy [`WuffsBorrowed::new`], and `work` must hold at least
+  /// [`WORK_BUF_LEN`](WuffsBmpDecoder::WORK_BUF_LEN) bytes.
+  pub fn from_buffers(
+    state: &'a mut [u8],
+    work: &'a mut [u8],
+  ) -> Result<Self, WuffsError> {
+    Self::with_work(WuffsBorrowed::new(state)?, work)
+  }
+}
+
+impl<B: WuffsStorage<wuffs_bmp__decoder>, W: AsMut<[u8]>> WuffsBmpDecoder<B, W> {
+  /// Initialize a decoder whose state lives in `inner` and whose work buffer is `work`,
+  /// which must hold at least [`WORK_BUF_LEN`](WuffsBmpDecoder::WORK_BUF_LEN) bytes.
+  pub fn with_work(mut inner: B, mut work: W) -> Result<Self, WuffsError> {
+    check_len(work.as_mut().len(), WORK_BUF_SIZE as usize)?;
+
+    unsafe {
+      wuffs_bmp__decoder__initialize(
+        inner.as_mut_ptr(),
+        inner.size() as _,
+        WUFFS_VERSION as _,
+        0,
+      )
+      .into_result()?;
+    }
+
+    Ok(Self {
+      work,
+      inner,
+      limits: WuffsLimits::default(),
+    })
+  }
+
+  /// Borrow `needed` bytes of work buffer.
+  fn work_buf(work: &mut W, needed: usize) -> Result<&mut [u8], WuffsError> {
+    check_len(work.as_mut().len(), needed)?;
+    Ok(work.as_mut())
+  }
+}
+
+image_decoder! {
+  WuffsBmpDecoder<W: AsMut<[u8]>>, wuffs_bmp__decoder {
+    sizeof: sizeof__wuffs_bmp__decoder,
+    set_quirk_enabled: wuffs_bmp__decoder__set_quirk_enabled,
+    set_quirk: wuffs_bmp__decoder__set_quirk,
+    set_report_metadata: wuffs_bmp__decoder__set_report_metadata,
+    tell_me_more: wuffs_bmp__decoder__tell_me_more,
+    decode_image_config: wuffs_bmp__decoder__decode_image_config,
+    decode_frame_config: wuffs_bmp__decoder__decode_frame_config,
+    decode_frame: wuffs_bmp__decoder__decode_frame,
+    num_animation_loops: wuffs_bmp__decoder__num_animation_loops,
+    num_decoded_frame_configs: wuffs_bmp__decoder__num_decoded_frame_configs,
+    num_decoded_frames: wuffs_bmp__decoder__num_decoded_frames,
+    workbuf_len: wuffs_bmp__decoder__workbuf_len,
+    frame_dirty_rect: wuffs_bmp__decoder__frame_dirty_rect,
+    restart_frame: wuffs_bmp__decoder__restart_frame,
+  }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+pub(crate) mod tests {
+  use super::WuffsBmpDecoder;
+  use crate::{
+    buf::WuffsBuf,
+    pixel::{WuffsPixelBuffer, WuffsPixelConfig, WuffsPixelFormat},
+    std::image::WuffsImageDecoder,
+    swizzler::WuffsPixelBlend,
+  };
+
+  // BLUE is a 1x1 24-bit BMP holding one blue pixel.
+  pub(crate) const BLUE: [u8; 58] = [
+    0x42, 0x4d, 0x3a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x36, 0x00, 0x00,
+    0x00, // 00..0d file header
+    0x28, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00,
+    0x18, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x13, 0x0b, 0x00, 0x00,
+    0x13, 0x0b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, // 0e..35 info header, 1x1 24 bpp
+    0xff, 0x00, 0x00, 0x00, // 36..39 blue as BGR, padded to 4 bytes
+  ];
+
+  #[test]
+  fn test_bmp_decode_frame() {
+    let mut src = unsafe { WuffsBuf::from_slice_readonly(&BLUE) };
+    let mut bmp = WuffsBmpDecoder::new().unwrap();
+
+    let image = bmp.decode_image_config(&mut src).unwrap();
+    assert_eq!((image.width(), image.height()), (1, 1));
+
+    bmp.decode_frame_config(&mut src).unwrap();
+
+    let config = WuffsPixelConfig::new(WuffsPixelFormat::RgbaNonpremul, 1, 1);
+    let mut buf = [0; 4];
+    let mut pixbuf = WuffsPixelBuffer::from_slice(config, &mut buf).unwrap();
+    bmp
+      .decode_frame(&mut pixbuf, &mut src, WuffsPixelBlend::Src)
+      .unwrap();
+
+    assert_eq!(pixbuf.pixel(0, 0), Some(&[0, 0, 0xff, 0xff][..]));
+  }
+}
@@ -0,0 +1,353 @@
+use core::{mem, time::Duration};
+
+use crate::pixel::WuffsPixelConfig;
+use wuffs_sys::{
+  wuffs_base__frame_config, wuffs_base__image_config, wuffs_base__more_information,
+  wuffs_base__rect_ie_u32, WUFFS_BASE__MORE_INFORMATION__FLAVOR__IO_REDIRECT,
+  WUFFS_BASE__MORE_INFORMATION__FLAVOR__IO_SEEK,
+  WUFFS_BASE__MORE_INFORMATION__FLAVOR__METADATA_PARSED,
+  WUFFS_BASE__MORE_INFORMATION__FLAVOR__METADATA_RAW_PASSTHROUGH,
+  WUFFS_BASE__MORE_INFORMATION__FLAVOR__METADATA_RAW_TRANSFORM,
+};
+
+/// `WUFFS_BASE__FLICKS_PER_SECOND`, a cast expression that bindgen doesn't export.
+const FLICKS_PER_SECOND: u128 = 705_600_000;
+
+/// Rectangle with inclusive minimum and exclusive maximum coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct WuffsRect {
+  pub min_x: u32,
+  pub min_y: u32,
+  pub max_x: u32,
+  pub max_y: u32,
+}
+
+impl WuffsRect {
+  pub fn from_inner(inner: wuffs_base__rect_ie_u32) -> Self {
+    Self {
+      min_x: inner.min_incl_x,
+      min_y: inner.min_incl_y,
+      max_x: inner.max_excl_x,
+      max_y: inner.max_excl_y,
+    }
+  }
+
+  pub fn width(&self) -> u32 {
+    self.max_x.saturating_sub(self.min_x)
+  }
+
+  pub fn height(&self) -> u32 {
+    self.max_y.saturating_sub(self.min_y)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.width() == 0 || self.height() == 0
+  }
+
+  /// Smallest rectangle holding both, ignoring empty ones.
+  pub fn union(&self, other: &Self) -> Self {
+    if self.is_empty() {
+      return *other;
+    }
+
+    if other.is_empty() {
+      return *self;
+    }
+
+    Self {
+      min_x: self.min_x.min(other.min_x),
+      min_y: self.min_y.min(other.min_y),
+      max_x: self.max_x.max(other.max_x),
+      max_y: self.max_y.max(other.max_y),
+    }
+  }
+
+  /// Smallest rectangle holding the part of this one that lies outside `other`.
+  pub fn difference(&self, other: &Self) -> Self {
+    let mut rect = *self;
+
+    if self.is_empty() || other.is_empty() {
+      return rect;
+    }
+
+    if other.min_x <= self.min_x && self.max_x <= other.max_x {
+      let (min_y, max_y) = trim(self.min_y, self.max_y, other.min_y, other.max_y);
+      rect.min_y = min_y;
+      rect.max_y = max_y;
+    } else if other.min_y <= self.min_y && self.max_y <= other.max_y {
+      let (min_x, max_x) = trim(self.min_x, self.max_x, other.min_x, other.max_x);
+      rect.min_x = min_x;
+      rect.max_x = max_x;
+    }
+
+    rect
+  }
+
+  /// Part of the rectangle that lies within `width` by `height`.
+  pub fn clip(&self, width: u32, height: u32) -> Self {
+    Self {
+      min_x: self.min_x.min(width),
+      min_y: self.min_y.min(height),
+      max_x: self.max_x.min(width),
+      max_y: self.max_y.min(height),
+    }
+  }
+}
+
+/// Smallest range holding the part of `min..max` outside `cut_min..cut_max`.  Only a cut
+/// reaching past one end shortens it.
+fn trim(min: u32, max: u32, cut_min: u32, cut_max: u32) -> (u32, u32) {
+  if cut_min <= min {
+    (cut_max.max(min).min(max), max)
+  } else if max <= cut_max {
+    (min, cut_min.min(max))
+  } else {
+    (min, max)
+  }
+}
+
+/// Dimensions and pixel format of an image, as reported before decoding any frame.
+#[derive(Debug, Clone, Copy)]
+pub struct WuffsImageConfig(wuffs_base__image_config);
+
+impl WuffsImageConfig {
+  pub fn from_inner(inner: wuffs_base__image_config) -> Self {
+    Self(inner)
+  }
+
+  pub fn into_inner(self) -> wuffs_base__image_config {
+    self.0
+  }
+
+  pub fn as_mut_ptr(&mut self) -> *mut wuffs_base__image_config {
+    &mut self.0 as *mut _
+  }
+
+  pub fn pixel_config(&self) -> WuffsPixelConfig {
+    WuffsPixelConfig::from_inner(self.0.pixcfg)
+  }
+
+  pub fn width(&self) -> u32 {
+    self.pixel_config().width()
+  }
+
+  pub fn height(&self) -> u32 {
+    self.pixel_config().height()
+  }
+
+  /// Position in the source of the first frame config, for restarting from frame 0.
+  pub fn first_frame_io_position(&self) -> u64 {
+    self.0.private_impl.first_frame_io_position
+  }
+
+  pub fn first_frame_is_opaque(&self) -> bool {
+    self.0.private_impl.first_frame_is_opaque
+  }
+}
+
+impl Default for WuffsImageConfig {
+  fn default() -> Self {
+    Self(unsafe { mem::zeroed() })
+  }
+}
+
+/// What happens to a frame's area once the next frame is due.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WuffsDisposal {
+  /// Leave the frame in place.
+  None,
+  /// Clear the frame's bounds to the background color.
+  RestoreBackground,
+  /// Put back what the canvas held before the frame was drawn.
+  RestorePrevious,
+}
+
+impl WuffsDisposal {
+  /// `WUFFS_BASE__ANIMATION_DISPOSAL__*` are cast expressions, which bindgen doesn't
+  /// export.  Unknown values are treated as `None`.
+  pub fn from_repr(repr: u8) -> Self {
+    match repr {
+      1 => Self::RestoreBackground,
+      2 => Self::RestorePrevious,
+      _ => Self::None,
+    }
+  }
+}
+
+/// Position, timing and compositing of one animation frame.
+#[derive(Debug, Clone, Copy)]
+pub struct WuffsFrameConfig(wuffs_base__frame_config);
+
+impl WuffsFrameConfig {
+  pub fn from_inner(inner: wuffs_base__frame_config) -> Self {
+    Self(inner)
+  }
+
+  pub fn into_inner(self) -> wuffs_base__frame_config {
+    self.0
+  }
+
+  pub fn as_mut_ptr(&mut self) -> *mut wuffs_base__frame_config {
+    &mut self.0 as *mut _
+  }
+
+  /// Area of the image the frame covers.
+  pub fn bounds(&self) -> WuffsRect {
+    WuffsRect::from_inner(self.0.private_impl.bounds)
+  }
+
+  /// How long the frame is shown, zero for still images.
+  pub fn duration(&self) -> Duration {
+    let flicks = self.0.private_impl.duration.max(0) as u128;
+    let nanos = flicks * 1_000_000_000 / FLICKS_PER_SECOND;
+
+    Duration::new((nanos / 1_000_000_000) as _, (nanos % 1_000_000_000) as _)
+  }
+
+  /// Zero-based index of the frame.
+  pub fn index(&self) -> u64 {
+    self.0.private_impl.index
+  }
+
+  /// Position in the source of the frame config, for seeking back to this frame.
+  pub fn io_position(&self) -> u64 {
+    self.0.private_impl.io_position
+  }
+
+  pub fn disposal(&self) -> WuffsDisposal {
+    WuffsDisposal::from_repr(self.0.private_impl.disposal as _)
+  }
+
+  /// Every pixel within the bounds is fully opaque.
+  pub fn opaque_within_bounds(&self) -> bool {
+    self.0.private_impl.opaque_within_bounds
+  }
+
+  /// Replace the pixels within the bounds rather than blending over them.
+  pub fn overwrite_instead_of_blend(&self) -> bool {
+    self.0.private_impl.overwrite_instead_of_blend
+  }
+
+  /// Background color as premultiplied ARGB, in a `u32` with blue in the low byte.
+  pub fn background_color(&self) -> u32 {
+    self.0.private_impl.background_color
+  }
+}
+
+impl Default for WuffsFrameConfig {
+  fn default() -> Self {
+    Self(unsafe { mem::zeroed() })
+  }
+}
+
+/// What a [`WuffsMoreInformation`] describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WuffsMoreInformationFlavor {
+  /// Nothing, the decoder left it unset.
+  None,
+  /// The rest of the input lives elsewhere.
+  IoRedirect,
+  /// Decoding continues at [`io_seek_position`](WuffsMoreInformation::io_seek_position).
+  IoSeek,
+  /// Metadata is the source bytes within
+  /// [`passthrough_range`](WuffsMoreInformation::passthrough_range).
+  MetadataRawPassthrough,
+  /// Metadata, decompressed or otherwise converted, was written to the destination.
+  MetadataRawTransform,
+  /// Metadata was parsed into the fields, read through the `parsed_*` methods.
+  MetadataParsed,
+  Other(u32),
+}
+
+/// Details behind a [`WuffsNote::MetadataReported`](crate::status::WuffsNote) or an I/O
+/// redirect, filled in by
+/// [`tell_me_more`](super::WuffsImageDecoder::tell_me_more).
+///
+/// The C accessors are static inline functions, which bindgen doesn't export, so they
+/// are reimplemented here over the raw fields.
+#[derive(Debug, Clone, Copy)]
+pub struct WuffsMoreInformation(wuffs_base__more_information);
+
+impl WuffsMoreInformation {
+  pub fn from_inner(inner: wuffs_base__more_information) -> Self {
+    Self(inner)
+  }
+
+  pub fn into_inner(self) -> wuffs_base__more_information {
+    self.0
+  }
+
+  pub fn as_mut_ptr(&mut self) -> *mut wuffs_base__more_information {
+    &mut self.0 as *mut _
+  }
+
+  pub fn flavor(&self) -> WuffsMoreInformationFlavor {
+    match self.0.flavor {
+      0 => WuffsMoreInformationFlavor::None,
+      WUFFS_BASE__MORE_INFORMATION__FLAVOR__IO_REDIRECT => {
+        WuffsMoreInformationFlavor::IoRedirect
+      }
+      WUFFS_BASE__MORE_INFORMATION__FLAVOR__IO_SEEK => WuffsMoreInformationFlavor::IoSeek,
+      WUFFS_BASE__MORE_INFORMATION__FLAVOR__METADATA_RAW_PASSTHROUGH => {
+        WuffsMoreInformationFlavor::MetadataRawPassthrough
+      }
+      WUFFS_BASE__MORE_INFORMATION__FLAVOR__METADATA_RAW_TRANSFORM => {
+        WuffsMoreInformationFlavor::MetadataRawTransform
+      }
+      WUFFS_BASE__MORE_INFORMATION__FLAVOR__METADATA_PARSED => {
+        WuffsMoreInformationFlavor::MetadataParsed
+      }
+      other => WuffsMoreInformationFlavor::Other(other),
+    }
+  }
+
+  /// Kind of metadata, one of the `WUFFS_BASE__FOURCC__*` constants.
+  pub fn fourcc(&self) -> u32 {
+    self.0.w
+  }
+
+  pub fn io_seek_position(&self) -> u64 {
+    self.0.x
+  }
+
+  /// Source positions of the metadata bytes, minimum inclusive and maximum exclusive.
+  pub fn passthrough_range(&self) -> (u64, u64) {
+    (self.0.y, self.0.z)
+  }
+
+  /// Inverse gamma scaled by 100000, e.g. 45455 for a gamma of 2.2.
+  pub fn parsed_gamma(&self) -> u32 {
+    self.0.x as _
+  }
+
+  /// sRGB rendering intent, 0 to 3 as in the PNG specification.
+  pub fn parsed_srgb(&self) -> u8 {
+    (self.0.x & 3) as _
+  }
+
+  /// Chromaticity scaled by 100000, `component` being 0 and 1 for the white point x and
+  /// y, then 2 to 7 for red, green and blue likewise.
+  pub fn parsed_chrm(&self, component: u32) -> i32 {
+    // The eight values are packed as 24-bit integers across x, y and z.
+    let (x, y, z) = (self.0.x, self.0.y, self.0.z);
+    let u = match component & 7 {
+      0 => x,
+      1 => x >> 24,
+      2 => (x >> 48) | (y << 16),
+      3 => y >> 8,
+      4 => y >> 32,
+      5 => (y >> 56) | (z << 8),
+      6 => z >> 16,
+      _ => z >> 40,
+    } as u32;
+
+    // Sign-extend from 24 bits.
+    ((u << 8) as i32) >> 8
+  }
+}
+
+impl Default for WuffsMoreInformation {
+  fn default() -> Self {
+    Self(unsafe { mem::zeroed() })
+  }
+}
@@ -0,0 +1,206 @@
+use super::{WuffsAnimatedDecoder, WuffsLimits};
+use crate::{
+  boxed::{check_len, WuffsBorrowed, WuffsDefault, WuffsStorage},
+  status::{IntoResult, WuffsError},
+};
+use wuffs_sys::{
+  wuffs_gif__decoder, wuffs_gif__decoder__initialize,
+  WUFFS_GIF__DECODER_WORKBUF_LEN_MAX_INCL_WORST_CASE as WORK_BUF_SIZE, WUFFS_VERSION,
+};
+
+pub struct WuffsGifDecoder<
+  B = WuffsDefault<wuffs_gif__decoder>,
+  W = [u8; WORK_BUF_SIZE as usize],
+> {
+  work: W,
+  inner: B,
+  limits: WuffsLimits,
+}
+
+impl WuffsGifDecoder {
+  /// Bytes of work buffer the decoder needs, see [`with_work`](Self::with_work).
+  pub const WORK_BUF_LEN: usize = WORK_BUF_SIZE as usize;
+
+  #[cfg(feature = "alloc")]
+  pub fn new() -> Result<Self, WuffsError> {
+    Self::with_storage(WuffsDefault::default())
+  }
+}
+
+impl<B: WuffsStorage<wuffs_gif__decoder>> WuffsGifDecoder<B> {
+  /// Initialize a decoder whose state lives in `inner`.
+  pub fn with_storage(inner: B) -> Result<Self, WuffsError> {
+    Self::with_work(inner, [0; WORK_BUF_SIZE as usize])
+  }
+}
+
+impl<'a> WuffsGifDecoder<WuffsBorrowed<'a, wuffs_gif__decoder>, &'a mut [u8]> {
+  /// Initialize a decoder without allocating: the state lives in `state`, which must
+  /// satisfy [`WuffsBorrowed::new`], and `work` must hold at least
+  /// [`WORK_BUF_LEN`](WuffsGifDecoder::WORK_BUF_LEN) bytes.
+  pub fn from_buffers(
+    state: &'a mut [u8],
+    work: &'a mut [u8],
+  ) -> Result<Self, WuffsError> {
+    Self::with_work(WuffsBorrowed::new(state)?, work)
+  }
+}
+
+impl<B: WuffsStorage<wuffs_gif__decoder>, W: AsMut<[u8]>> WuffsGifDecoder<B, W> {
+  /// Initialize a decoder whose state lives in `inner` and whose work buffer is `work`,
+  /// which must hold at least [`WORK_BUF_LEN`](WuffsGifDecoder::WORK_BUF_LEN) bytes.
+  pub fn with_work(mut inner: B, mut work: W) -> Result<Self, WuffsError> {
+    check_len(work.as_mut().len(), WORK_BUF_SIZE as usize)?;
+
+    unsafe {
+      wuffs_gif__decoder__initialize(
+        inner.as_mut_ptr(),
+        inner.size() as _,
+        WUFFS_VERSION as _,
+        0,
+      )
+      .into_result()?;
+    }
+
+    Ok(Self {
+      work,
+      inner,
+      limits: WuffsLimits::default(),
+    })
+  }
+
+  /// Borrow `needed` bytes of work buffer.
+  fn work_buf(work: &mut W, needed: usize) -> Result<&mut [u8], WuffsError> {
+    check_len(work.as_mut().len(), needed)?;
+    Ok(work.as_mut())
+  }
+}
+
+image_decoder! {
+  WuffsGifDecoder<W: AsMut<[u8]>>, wuffs_gif__decoder {
+    sizeof: sizeof__wuffs_gif__decoder,
+    set_quirk_enabled: wuffs_gif__decoder__set_quirk_enabled,
+    set_quirk: wuffs_gif__decoder__set_quirk,
+    set_report_metadata: wuffs_gif__decoder__set_report_metadata,
+    tell_me_more: wuffs_gif__decoder__tell_me_more,
+    decode_image_config: wuffs_gif__decoder__decode_image_config,
+    decode_frame_config: wuffs_gif__decoder__decode_frame_config,
+    decode_frame: wuffs_gif__decoder__decode_frame,
+    num_animation_loops: wuffs_gif__decoder__num_animation_loops,
+    num_decoded_frame_configs: wuffs_gif__decoder__num_decoded_frame_configs,
+    num_decoded_frames: wuffs_gif__decoder__num_decoded_frames,
+    workbuf_len: wuffs_gif__decoder__workbuf_len,
+    frame_dirty_rect: wuffs_gif__decoder__frame_dirty_rect,
+    restart_frame: wuffs_gif__decoder__restart_frame,
+  }
+}
+
+impl<B: WuffsStorage<wuffs_gif__decoder>, W: AsMut<[u8]>> WuffsAnimatedDecoder
+  for WuffsGifDecoder<B, W>
+{
+}
+
+#[cfg(all(test, feature = "alloc"))]
+pub(crate) mod tests {
+  use super::WuffsGifDecoder;
+  use crate::{
+    buf::WuffsBuf,
+    pixel::{WuffsPixelBuffer, WuffsPixelConfig, WuffsPixelFormat},
+    status::WuffsError,
+    std::image::{WuffsDisposal, WuffsImageDecoder, WuffsLimit, WuffsLimits},
+    swizzler::WuffsPixelBlend,
+  };
+
+  // ANIMATED is a 2x2 GIF with a global palette of transparent, red, green and blue and
+  // two frames, each with a 100ms delay, looping forever:
+  //   - frame 0 covers the whole image with red pixels, disposal none,
+  //   - frame 1 covers the bottom right pixel in green, restore background.
+  pub(crate) const ANIMATED: [u8; 91] = [
+    0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x02, 0x00, 0x02,
+    0x00, // 00..09 header, 2x2
+    0xf1, 0x00, 0x00, // 0a..0c four color global palette
+    0x00, 0x00, 0x00, 0xff, 0x00, 0x00, 0x00, 0xff, 0x00, 0x00, 0x00,
+    0xff, // 0d..18
+    0x21, 0xff, 0x0b, 0x4e, 0x45, 0x54, 0x53, 0x43, 0x41, 0x50, 0x45, 0x32, 0x2e, 0x30,
+    0x03, 0x01, 0x00, 0x00, 0x00, // 19..2b NETSCAPE2.0, loop forever
+    0x21, 0xf9, 0x04, 0x05, 0x0a, 0x00, 0x00, 0x00, // 2c..33 keep, 100ms
+    0x2c, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x02, 0x00, 0x00, // 34..3d 0,0 2x2
+    0x02, 0x02, 0x8c, 0x53, 0x00, // 3e..42 LZW of 1 1 1 1
+    0x21, 0xf9, 0x04, 0x09, 0x0a, 0x00, 0x00,
+    0x00, // 43..4a restore background, 100ms
+    0x2c, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, // 4b..54 1,1 1x1
+    0x02, 0x02, 0x54, 0x01, 0x00, // 55..59 LZW of 2
+    0x3b, // 5a trailer
+  ];
+
+  #[test]
+  fn test_gif_decode_frames() {
+    let mut src = unsafe { WuffsBuf::from_slice_readonly(&ANIMATED) };
+    let mut gif = WuffsGifDecoder::new().unwrap();
+
+    let image = gif.decode_image_config(&mut src).unwrap();
+    assert_eq!((image.width(), image.height()), (2, 2));
+
+    let frame = gif.decode_frame_config(&mut src).unwrap();
+    assert_eq!(frame.index(), 0);
+    assert_eq!(frame.disposal(), WuffsDisposal::None);
+    assert_eq!(frame.duration().as_millis(), 100);
+
+    let config = WuffsPixelConfig::new(WuffsPixelFormat::RgbaNonpremul, 2, 2);
+    let mut buf = [0; 16];
+    let mut pixbuf = WuffsPixelBuffer::from_slice(config, &mut buf).unwrap();
+    gif
+      .decode_frame(&mut pixbuf, &mut src, WuffsPixelBlend::Src)
+      .unwrap();
+
+    assert_eq!(pixbuf.pixel(1, 1), Some(&[0xff, 0, 0, 0xff][..]));
+    assert_eq!(gif.num_animation_loops(), 0);
+    assert_eq!(gif.num_decoded_frames(), 1);
+  }
+
+  #[test]
+  fn test_gif_limits() {
+    let mut src = unsafe { WuffsBuf::from_slice_readonly(&ANIMATED) };
+    let mut gif = WuffsGifDecoder::new().unwrap();
+    gif.set_limits(WuffsLimits {
+      max_pixels: Some(3),
+      ..Default::default()
+    });
+
+    assert_eq!(
+      gif.decode_image_config(&mut src).unwrap_err(),
+      WuffsError::LimitExceeded {
+        limit: WuffsLimit::Pixels,
+        value: 4,
+        max: 3,
+      }
+    );
+  }
+
+  #[test]
+  fn test_gif_decode_frame_into() {
+    let mut src = unsafe { WuffsBuf::from_slice_readonly(&ANIMATED) };
+    let mut gif = WuffsGifDecoder::new().unwrap();
+
+    let image = gif.decode_image_config(&mut src).unwrap();
+    gif.decode_frame_config(&mut src).unwrap();
+
+    // Two rows of two BGRA pixels, 12 bytes apart.
+    let mut framebuffer = [0xaa; 20];
+    gif
+      .decode_frame_into(
+        &image,
+        &mut framebuffer,
+        12,
+        WuffsPixelFormat::BgraNonpremul,
+        &mut src,
+        WuffsPixelBlend::Src,
+      )
+      .unwrap();
+
+    let red = [0, 0, 0xff, 0xff];
+    assert_eq!(framebuffer[..8], [red, red].concat()[..]);
+    assert_eq!(framebuffer[8..12], [0xaa; 4]);
+    assert_eq!(framebuffer[12..], [red, red].concat()[..]);
+  }
+}
@@ -0,0 +1,281 @@
+use std::{boxed::Box, iter, vec::Vec};
+
+use super::{
+  WuffsAnimatedDecoder, WuffsFrame, WuffsFrames, WuffsImageConfig, WuffsImageDecoder,
+  WuffsImageFormat, WuffsLimit, WuffsLimits, WuffsMetadata,
+};
+use crate::{
+  buf::WuffsBuf, pixel::WuffsPixelFormat, status::WuffsError, swizzler::WuffsPixelBlend,
+};
+use ::image::{
+  error::{DecodingError, ImageFormatHint, LimitError, LimitErrorKind},
+  AnimationDecoder, ColorType, Delay, Frame, Frames, ImageDecoder, ImageError,
+  ImageFormat, ImageResult, LimitSupport, Limits, RgbaImage,
+};
+
+/// Adapter from a wuffs image decoder to the `image` crate's [`ImageDecoder`] and
+/// [`AnimationDecoder`], for an image held in memory.
+///
+/// Pixels are always produced as [`ColorType::Rgba8`].  `read_image` yields the first
+/// frame, `into_frames` every frame composited onto the full canvas, as the `image`
+/// crate's own GIF and APNG decoders do.  `into_frames` is only there for
+/// [animated formats](WuffsAnimatedDecoder).
+///
+/// [`set_limits`](ImageDecoder::set_limits) installs the `image` crate's limits on the
+/// decoder, so `max_alloc` also bounds the work buffer and the canvas of every frame.
+///
+/// ```ignore
+/// let decoder = WuffsImageCrateDecoder::new(WuffsPngDecoder::new()?, &bytes)?;
+/// let image = image::DynamicImage::from_decoder(decoder)?;
+/// ```
+pub struct WuffsImageCrateDecoder<'a, D> {
+  decoder: D,
+  src: WuffsBuf,
+  image: WuffsImageConfig,
+  metadata: WuffsMetadata,
+  format: ImageFormatHint,
+  _src: &'a [u8],
+}
+
+impl<'a, D: WuffsImageDecoder> WuffsImageCrateDecoder<'a, D> {
+  /// Decode the image config of `src` with a freshly initialized `decoder`.
+  pub fn new(mut decoder: D, src: &'a [u8]) -> ImageResult<Self> {
+    let format = match WuffsImageFormat::detect(src) {
+      Ok(format) => ImageFormatHint::Exact(image_format(format)),
+      Err(_) => ImageFormatHint::Unknown,
+    };
+
+    let mut buf = unsafe { WuffsBuf::from_slice_readonly(src) };
+    let mut metadata = WuffsMetadata::default();
+    WuffsMetadata::enable(&mut decoder);
+    let image = metadata
+      .decode_image_config(&mut decoder, &mut buf)
+      .map_err(|err| error(&format, err))?;
+
+    Ok(Self {
+      decoder,
+      src: buf,
+      image,
+      metadata,
+      format,
+      _src: src,
+    })
+  }
+
+  /// Metadata reported before the first frame.
+  pub fn metadata(&self) -> &WuffsMetadata {
+    &self.metadata
+  }
+
+  pub fn into_inner(self) -> D {
+    self.decoder
+  }
+
+  fn decode_first_frame(&mut self, buf: &mut [u8]) -> Result<(), WuffsError> {
+    let stride = self.image.width() as usize * 4;
+
+    self
+      .metadata
+      .decode_frame_config(&mut self.decoder, &mut self.src)?;
+    self.decoder.decode_frame_into(
+      &self.image,
+      buf,
+      stride,
+      WuffsPixelFormat::RgbaNonpremul,
+      &mut self.src,
+      WuffsPixelBlend::Src,
+    )
+  }
+}
+
+impl<D: WuffsImageDecoder> ImageDecoder for WuffsImageCrateDecoder<'_, D> {
+  fn dimensions(&self) -> (u32, u32) {
+    (self.image.width(), self.image.height())
+  }
+
+  fn color_type(&self) -> ColorType {
+    ColorType::Rgba8
+  }
+
+  fn icc_profile(&mut self) -> ImageResult<Option<Vec<u8>>> {
+    Ok(self.metadata.icc_profile.clone())
+  }
+
+  fn set_limits(&mut self, limits: Limits) -> ImageResult<()> {
+    limits.check_support(&LimitSupport::default())?;
+
+    let (width, height) = self.dimensions();
+    limits.check_dimensions(width, height)?;
+
+    match limits.max_alloc {
+      Some(max) if self.total_bytes() > max => {
+        return Err(ImageError::Limits(LimitError::from_kind(
+          LimitErrorKind::InsufficientMemory,
+        )))
+      }
+      _ => {}
+    }
+
+    let current = self.decoder.limits();
+    let wuffs = WuffsLimits {
+      max_width: tighter(current.max_width, limits.max_image_width),
+      max_height: tighter(current.max_height, limits.max_image_height),
+      max_bytes: tighter(current.max_bytes, limits.max_alloc),
+      ..current
+    };
+
+    // The image config was decoded before these limits arrived.
+    wuffs
+      .check_work(self.decoder.workbuf_len() as u64)
+      .map_err(|err| error(&self.format, err))?;
+    self.decoder.set_limits(wuffs);
+
+    Ok(())
+  }
+
+  fn read_image(mut self, buf: &mut [u8]) -> ImageResult<()> {
+    assert_eq!(buf.len() as u64, self.total_bytes());
+
+    // Pixels outside a first frame smaller than the canvas stay transparent.
+    buf.fill(0);
+    self
+      .decode_first_frame(buf)
+      .map_err(|err| error(&self.format, err))
+  }
+
+  fn read_image_boxed(self: Box<Self>, buf: &mut [u8]) -> ImageResult<()> {
+    (*self).read_image(buf)
+  }
+}
+
+impl<'a, D: WuffsAnimatedDecoder + 'a> AnimationDecoder<'a>
+  for WuffsImageCrateDecoder<'a, D>
+{
+  fn into_frames(self) -> Frames<'a> {
+    let format = self.format;
+
+    match WuffsFrames::with_config(self.decoder, self.src, self.image, self.metadata) {
+      Ok(frames) => {
+        Frames::new(Box::new(frames.map(move |result| {
+          result.map(frame).map_err(|err| error(&format, err))
+        })))
+      }
+      Err(err) => Frames::new(Box::new(iter::once(Err(error(&format, err))))),
+    }
+  }
+}
+
+fn frame(frame: WuffsFrame) -> Frame {
+  let buffer = RgbaImage::from_raw(frame.width, frame.height, frame.pixels)
+    .expect("frames cover the whole canvas");
+
+  Frame::from_parts(
+    buffer,
+    0,
+    0,
+    Delay::from_saturating_duration(frame.duration),
+  )
+}
+
+fn image_format(format: WuffsImageFormat) -> ImageFormat {
+  match format {
+    #[cfg(feature = "bmp")]
+    WuffsImageFormat::Bmp => ImageFormat::Bmp,
+    #[cfg(feature = "gif")]
+    WuffsImageFormat::Gif => ImageFormat::Gif,
+    #[cfg(feature = "jpeg")]
+    WuffsImageFormat::Jpeg => ImageFormat::Jpeg,
+    #[cfg(feature = "png")]
+    WuffsImageFormat::Png => ImageFormat::Png,
+    #[cfg(feature = "qoi")]
+    WuffsImageFormat::Qoi => ImageFormat::Qoi,
+    #[cfg(feature = "targa")]
+    WuffsImageFormat::Targa => ImageFormat::Tga,
+  }
+}
+
+/// The smaller of two optional limits.
+fn tighter<T: Ord>(a: Option<T>, b: Option<T>) -> Option<T> {
+  match (a, b) {
+    (Some(a), Some(b)) => Some(a.min(b)),
+    (a, b) => a.or(b),
+  }
+}
+
+fn error(format: &ImageFormatHint, err: WuffsError) -> ImageError {
+  match err {
+    WuffsError::LimitExceeded {
+      limit: WuffsLimit::Bytes | WuffsLimit::WorkBuffer,
+      ..
+    } => ImageError::Limits(LimitError::from_kind(LimitErrorKind::InsufficientMemory)),
+    WuffsError::LimitExceeded { .. } => {
+      ImageError::Limits(LimitError::from_kind(LimitErrorKind::DimensionError))
+    }
+    err => ImageError::Decoding(DecodingError::new(format.clone(), err)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  #[cfg(feature = "png")]
+  fn test_dynamic_image_png() {
+    use super::WuffsImageCrateDecoder;
+    use crate::std::image::png::{tests::RED, WuffsPngDecoder};
+    use ::image::{DynamicImage, ImageDecoder};
+
+    let mut decoder =
+      WuffsImageCrateDecoder::new(WuffsPngDecoder::new().unwrap(), &RED).unwrap();
+    assert_eq!(
+      decoder.icc_profile().unwrap().as_deref(),
+      Some(&b"profile"[..])
+    );
+
+    let image = DynamicImage::from_decoder(decoder).unwrap().into_rgba8();
+    assert_eq!(image.dimensions(), (1, 1));
+    assert_eq!(image.get_pixel(0, 0).0, [0xff, 0, 0, 0xff]);
+  }
+
+  #[test]
+  #[cfg(feature = "png")]
+  fn test_png_max_alloc() {
+    use super::WuffsImageCrateDecoder;
+    use crate::std::image::{
+      png::{tests::RED, WuffsPngDecoder},
+      WuffsImageDecoder,
+    };
+    use ::image::{ImageDecoder, ImageError, Limits};
+
+    let mut decoder =
+      WuffsImageCrateDecoder::new(WuffsPngDecoder::new().unwrap(), &RED).unwrap();
+    let mut limits = Limits::default();
+    limits.max_alloc = Some(5);
+    decoder.set_limits(limits.clone()).unwrap();
+    assert_eq!(decoder.into_inner().limits().max_bytes, Some(5));
+
+    // The pixel fits, the work buffer and its filter byte don't.
+    let mut decoder =
+      WuffsImageCrateDecoder::new(WuffsPngDecoder::new().unwrap(), &RED).unwrap();
+    limits.max_alloc = Some(4);
+    assert!(matches!(
+      decoder.set_limits(limits),
+      Err(ImageError::Limits(_))
+    ));
+  }
+
+  #[test]
+  #[cfg(feature = "gif")]
+  fn test_gif_frames() {
+    use super::WuffsImageCrateDecoder;
+    use crate::std::image::gif::{tests::ANIMATED, WuffsGifDecoder};
+    use ::image::AnimationDecoder;
+
+    let decoder =
+      WuffsImageCrateDecoder::new(WuffsGifDecoder::new().unwrap(), &ANIMATED).unwrap();
+    let frames = decoder.into_frames().collect_frames().unwrap();
+
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[1].buffer().get_pixel(1, 1).0, [0, 0xff, 0, 0xff]);
+    assert_eq!(frames[1].delay().numer_denom_ms(), (100, 1));
+  }
+}
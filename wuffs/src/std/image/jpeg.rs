@@ -0,0 +1,142 @@
+use super::WuffsLimits;
+use crate::{
+  boxed::{WuffsBorrowed, WuffsDefault, WuffsHeapWork, WuffsStorage, WuffsWork},
+  status::{IntoResult, WuffsError},
+};
+use wuffs_sys::{wuffs_jpeg__decoder, wuffs_jpeg__decoder__initialize, WUFFS_VERSION};
+
+/// Baseline and progressive JPEG decoder.
+///
+/// The work buffer holds the decoded components, so its size is only known once the
+/// image config is decoded.  A heap work buffer grows to fit; a slice must already be
+/// large enough, see [`workbuf_len`](super::WuffsImageDecoder::workbuf_len).
+pub struct WuffsJpegDecoder<B = WuffsDefault<wuffs_jpeg__decoder>, W = WuffsHeapWork> {
+  work: W,
+  inner: B,
+  limits: WuffsLimits,
+}
+
+impl WuffsJpegDecoder {
+  #[cfg(feature = "alloc")]
+  pub fn new() -> Result<Self, WuffsError> {
+    Self::with_storage(WuffsDefault::default())
+  }
+}
+
+impl<B: WuffsStorage<wuffs_jpeg__decoder>> WuffsJpegDecoder<B> {
+  /// Initialize a decoder whose state lives in `inner`, with a heap work buffer.
+  #[cfg(feature = "alloc")]
+  pub fn with_storage(inner: B) -> Result<Self, WuffsError> {
+    Self::with_work(inner, WuffsHeapWork::default())
+  }
+}
+
+impl<'a> WuffsJpegDecoder<WuffsBorrowed<'a, wuffs_jpeg__decoder>, &'a mut [u8]> {
+  /// Initialize a decoder without allocating: the state lives in `state`, which must
+  /// satisfy [`WuffsBorrowed::new`], and `work` is the work buffer.
+  pub fn from_buffers(
+    state: &'a mut [u8],
+    work: &'a mut [u8],
+  ) -> Result<Self, WuffsError> {
+    Self::with_work(WuffsBorrowed::new(state)?, work)
+  }
+}
+
+impl<B: WuffsStorage<wuffs_jpeg__decoder>, W: WuffsWork> WuffsJpegDecoder<B, W> {
+  /// Initialize a decoder whose state lives in `inner` and whose work buffer is `work`.
+  pub fn with_work(mut inner: B, work: W) -> Result<Self, WuffsError> {
+    unsafe {
+      wuffs_jpeg__decoder__initialize(
+        inner.as_mut_ptr(),
+        inner.size() as _,
+        WUFFS_VERSION as _,
+        0,
+      )
+      .into_result()?;
+    }
+
+    Ok(Self {
+      work,
+      inner,
+      limits: WuffsLimits::default(),
+    })
+  }
+
+  /// Borrow `needed` bytes of work buffer, growing it if it can be.
+  fn work_buf(work: &mut W, needed: usize) -> Result<&mut [u8], WuffsError> {
+    work.reserve(needed)
+  }
+}
+
+image_decoder! {
+  WuffsJpegDecoder<W: WuffsWork>, wuffs_jpeg__decoder {
+    sizeof: sizeof__wuffs_jpeg__decoder,
+    set_quirk_enabled: wuffs_jpeg__decoder__set_quirk_enabled,
+    set_quirk: wuffs_jpeg__decoder__set_quirk,
+    set_report_metadata: wuffs_jpeg__decoder__set_report_metadata,
+    tell_me_more: wuffs_jpeg__decoder__tell_me_more,
+    decode_image_config: wuffs_jpeg__decoder__decode_image_config,
+    decode_frame_config: wuffs_jpeg__decoder__decode_frame_config,
+    decode_frame: wuffs_jpeg__decoder__decode_frame,
+    num_animation_loops: wuffs_jpeg__decoder__num_animation_loops,
+    num_decoded_frame_configs: wuffs_jpeg__decoder__num_decoded_frame_configs,
+    num_decoded_frames: wuffs_jpeg__decoder__num_decoded_frames,
+    workbuf_len: wuffs_jpeg__decoder__workbuf_len,
+    frame_dirty_rect: wuffs_jpeg__decoder__frame_dirty_rect,
+    restart_frame: wuffs_jpeg__decoder__restart_frame,
+  }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+pub(crate) mod tests {
+  use super::WuffsJpegDecoder;
+  use crate::{
+    buf::WuffsBuf,
+    pixel::{WuffsPixelBuffer, WuffsPixelConfig, WuffsPixelFormat},
+    std::image::WuffsImageDecoder,
+    swizzler::WuffsPixelBlend,
+  };
+
+  // GRAY is an 8x8 baseline JPEG of one mid-gray block: every coefficient is zero, so
+  // every sample is the 128 level shift.
+  pub(crate) const GRAY: [u8; 141] = [
+    0xff, 0xd8, // 00..01 SOI
+    0xff, 0xdb, 0x00, 0x43, 0x00, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+    0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+    0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+    0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+    0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+    0x01, // 02..46 DQT, every quantizer 1
+    0xff, 0xc0, 0x00, 0x0b, 0x08, 0x00, 0x08, 0x00, 0x08, 0x01, 0x01, 0x11,
+    0x00, // 47..53 SOF0 8x8, one component
+    0xff, 0xc4, 0x00, 0x14, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, // 54..69 DHT DC, one code for category 0
+    0xff, 0xc4, 0x00, 0x14, 0x10, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, // 6a..7f DHT AC, one code for EOB
+    0xff, 0xda, 0x00, 0x08, 0x01, 0x01, 0x00, 0x00, 0x3f, 0x00, // 80..89 SOS
+    0x3f, // 8a DC diff 0 then EOB, padded with ones
+    0xff, 0xd9, // 8b..8c EOI
+  ];
+
+  #[test]
+  fn test_jpeg_decode_frame() {
+    let mut src = unsafe { WuffsBuf::from_slice_readonly(&GRAY) };
+    let mut jpeg = WuffsJpegDecoder::new().unwrap();
+
+    let image = jpeg.decode_image_config(&mut src).unwrap();
+    assert_eq!((image.width(), image.height()), (8, 8));
+
+    jpeg.decode_frame_config(&mut src).unwrap();
+
+    let config = WuffsPixelConfig::new(WuffsPixelFormat::RgbaNonpremul, 8, 8);
+    let mut buf = [0; 256];
+    let mut pixbuf = WuffsPixelBuffer::from_slice(config, &mut buf).unwrap();
+    jpeg
+      .decode_frame(&mut pixbuf, &mut src, WuffsPixelBlend::Src)
+      .unwrap();
+
+    assert_eq!(pixbuf.pixel(0, 0), Some(&[0x80, 0x80, 0x80, 0xff][..]));
+  }
+}
@@ -0,0 +1,147 @@
+use core::fmt::{self, Display};
+
+use crate::{pixel::WuffsPixelConfig, status::WuffsError};
+
+/// Quantity a [`WuffsLimits`] bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WuffsLimit {
+  Width,
+  Height,
+  /// Width times height.
+  Pixels,
+  /// Bytes of a pixel buffer holding the whole image.
+  Bytes,
+  /// Bytes of work buffer the decoder needs, bounded by `max_bytes` too.
+  WorkBuffer,
+}
+
+impl Display for WuffsLimit {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(match self {
+      Self::Width => "width",
+      Self::Height => "height",
+      Self::Pixels => "pixel count",
+      Self::Bytes => "byte size",
+      Self::WorkBuffer => "work buffer size",
+    })
+  }
+}
+
+/// Upper bounds on the size of images a decoder accepts, checked as soon as the image
+/// config is decoded so that no pixel memory is allocated for an image that breaks them.
+///
+/// Every limit is off by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct WuffsLimits {
+  pub max_width: Option<u32>,
+  pub max_height: Option<u32>,
+  pub max_pixels: Option<u64>,
+  /// Bounds the pixel buffer and, separately, the decoder's work buffer.
+  pub max_bytes: Option<u64>,
+}
+
+impl WuffsLimits {
+  /// Fail with [`WuffsError::LimitExceeded`] naming the first limit `config` breaks.
+  ///
+  /// Bytes are counted in the config's own pixel format, or at eight per pixel, the
+  /// most wuffs uses, for formats this crate doesn't model.
+  pub fn check(&self, config: &WuffsPixelConfig) -> Result<(), WuffsError> {
+    let pixels = config.width() as u64 * config.height() as u64;
+    let bytes = match config.format() {
+      Some(_) => config.pixbuf_len().map_or(u64::MAX, |len| len as u64),
+      None => pixels.saturating_mul(8),
+    };
+
+    let checks = [
+      (
+        WuffsLimit::Width,
+        config.width() as u64,
+        self.max_width.map(u64::from),
+      ),
+      (
+        WuffsLimit::Height,
+        config.height() as u64,
+        self.max_height.map(u64::from),
+      ),
+      (WuffsLimit::Pixels, pixels, self.max_pixels),
+      (WuffsLimit::Bytes, bytes, self.max_bytes),
+    ];
+
+    for &(limit, value, max) in &checks {
+      exceeds(limit, value, max)?;
+    }
+
+    Ok(())
+  }
+
+  /// Fail with [`WuffsError::LimitExceeded`] when a work buffer of `len` bytes is over
+  /// `max_bytes`.
+  pub fn check_work(&self, len: u64) -> Result<(), WuffsError> {
+    exceeds(WuffsLimit::WorkBuffer, len, self.max_bytes)
+  }
+
+  /// Fail with [`WuffsError::LimitExceeded`] when `bytes` of pixels are over
+  /// `max_bytes`.
+  #[cfg(feature = "alloc")]
+  pub(crate) fn check_bytes(&self, bytes: u64) -> Result<(), WuffsError> {
+    exceeds(WuffsLimit::Bytes, bytes, self.max_bytes)
+  }
+}
+
+fn exceeds(limit: WuffsLimit, value: u64, max: Option<u64>) -> Result<(), WuffsError> {
+  match max {
+    Some(max) if value > max => Err(WuffsError::LimitExceeded { limit, value, max }),
+    _ => Ok(()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{WuffsLimit, WuffsLimits};
+  use crate::{
+    pixel::{WuffsPixelConfig, WuffsPixelFormat},
+    status::WuffsError,
+  };
+
+  #[test]
+  fn test_limits() {
+    let config = WuffsPixelConfig::new(WuffsPixelFormat::RgbaNonpremul, 300, 200);
+    assert_eq!(WuffsLimits::default().check(&config), Ok(()));
+
+    let limits = WuffsLimits {
+      max_width: Some(300),
+      max_pixels: Some(60_000),
+      max_bytes: Some(200_000),
+      ..Default::default()
+    };
+    assert_eq!(
+      limits.check(&config),
+      Err(WuffsError::LimitExceeded {
+        limit: WuffsLimit::Bytes,
+        value: 240_000,
+        max: 200_000,
+      })
+    );
+
+    let limits = WuffsLimits {
+      max_height: Some(100),
+      ..limits
+    };
+    assert_eq!(
+      limits.check(&config),
+      Err(WuffsError::LimitExceeded {
+        limit: WuffsLimit::Height,
+        value: 200,
+        max: 100,
+      })
+    );
+    assert_eq!(
+      limits.check_work(300_000),
+      Err(WuffsError::LimitExceeded {
+        limit: WuffsLimit::WorkBuffer,
+        value: 300_000,
+        max: 200_000,
+      })
+    );
+  }
+}
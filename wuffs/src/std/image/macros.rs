@@ -0,0 +1,232 @@
+/// Implement [`WuffsImageDecoder`](super::WuffsImageDecoder), the quirk setters, `fork`
+/// and the storage traits for a decoder over the wuffs state `$state`, by calling the
+/// listed `wuffs_sys` functions.
+///
+/// The decoder must have `work`, `inner` and `limits` fields, and a `work_buf` function
+/// that borrows at least the given number of bytes from `work` for
+/// [`decode_frame`](super::WuffsImageDecoder::decode_frame).
+macro_rules! image_decoder {
+  (
+    $decoder:ident<W: $work:path>, $state:ident {
+      sizeof: $sizeof:ident,
+      set_quirk_enabled: $set_quirk_enabled:ident,
+      set_quirk: $set_quirk:ident,
+      set_report_metadata: $set_report_metadata:ident,
+      tell_me_more: $tell_me_more:ident,
+      decode_image_config: $decode_image_config:ident,
+      decode_frame_config: $decode_frame_config:ident,
+      decode_frame: $decode_frame:ident,
+      num_animation_loops: $num_animation_loops:ident,
+      num_decoded_frame_configs: $num_decoded_frame_configs:ident,
+      num_decoded_frames: $num_decoded_frames:ident,
+      workbuf_len: $workbuf_len:ident,
+      frame_dirty_rect: $frame_dirty_rect:ident,
+      restart_frame: $restart_frame:ident,
+    }
+  ) => {
+    impl<B: $crate::boxed::WuffsStorage<$state>, W: $work> $decoder<B, W> {
+      /// Turn `quirk` on or off.  wuffs v0.4 replaced boolean quirks with `set_quirk`,
+      /// available with the `v0_4` feature, which also reports quirks the decoder doesn't
+      /// support; they are ignored here.
+      pub fn set_quirk_enabled(&mut self, quirk: u32, enabled: bool) {
+        #[cfg(not(feature = "v0_4"))]
+        unsafe {
+          ::wuffs_sys::$set_quirk_enabled(self.inner.as_mut_ptr(), quirk, enabled);
+        }
+
+        #[cfg(feature = "v0_4")]
+        let _ = self.set_quirk(quirk, enabled as u64);
+      }
+
+      /// Set the quirk `key` to `value`, failing if the decoder doesn't support it.
+      #[cfg(feature = "v0_4")]
+      pub fn set_quirk(
+        &mut self,
+        key: u32,
+        value: u64,
+      ) -> Result<(), $crate::status::WuffsError> {
+        use $crate::status::IntoResult;
+
+        unsafe {
+          ::wuffs_sys::$set_quirk(self.inner.as_mut_ptr(), key, value).into_result()
+        }
+      }
+    }
+
+    impl<B: $crate::boxed::WuffsStorage<$state>, W: $work>
+      $crate::std::image::WuffsImageDecoder for $decoder<B, W>
+    {
+      fn set_report_metadata(&mut self, fourcc: u32, report: bool) {
+        unsafe {
+          ::wuffs_sys::$set_report_metadata(self.inner.as_mut_ptr(), fourcc, report);
+        }
+      }
+
+      fn tell_me_more(
+        &mut self,
+        dst: &mut $crate::buf::WuffsBuf,
+        minfo: &mut $crate::std::image::WuffsMoreInformation,
+        src: &mut $crate::buf::WuffsBuf,
+      ) -> Result<(), $crate::status::WuffsError> {
+        use $crate::status::IntoResult;
+
+        unsafe {
+          ::wuffs_sys::$tell_me_more(
+            self.inner.as_mut_ptr(),
+            dst.as_mut_ptr(),
+            minfo.as_mut_ptr(),
+            src.as_mut_ptr(),
+          )
+          .into_result()
+        }
+      }
+
+      fn limits(&self) -> $crate::std::image::WuffsLimits {
+        self.limits
+      }
+
+      fn set_limits(&mut self, limits: $crate::std::image::WuffsLimits) {
+        self.limits = limits;
+      }
+
+      fn decode_image_config(
+        &mut self,
+        src: &mut $crate::buf::WuffsBuf,
+      ) -> Result<$crate::std::image::WuffsImageConfig, $crate::status::WuffsError> {
+        use $crate::status::IntoResult;
+
+        let mut config = $crate::std::image::WuffsImageConfig::default();
+
+        unsafe {
+          ::wuffs_sys::$decode_image_config(
+            self.inner.as_mut_ptr(),
+            config.as_mut_ptr(),
+            src.as_mut_ptr(),
+          )
+          .into_result()?;
+        }
+
+        self.limits.check(&config.pixel_config())?;
+        self
+          .limits
+          .check_work($crate::std::image::WuffsImageDecoder::workbuf_len(self) as u64)?;
+
+        Ok(config)
+      }
+
+      fn decode_frame_config(
+        &mut self,
+        src: &mut $crate::buf::WuffsBuf,
+      ) -> Result<$crate::std::image::WuffsFrameConfig, $crate::status::WuffsError> {
+        use $crate::status::IntoResult;
+
+        let mut config = $crate::std::image::WuffsFrameConfig::default();
+
+        unsafe {
+          ::wuffs_sys::$decode_frame_config(
+            self.inner.as_mut_ptr(),
+            config.as_mut_ptr(),
+            src.as_mut_ptr(),
+          )
+          .into_result()?;
+        }
+
+        Ok(config)
+      }
+
+      fn decode_frame(
+        &mut self,
+        dst: &mut $crate::pixel::WuffsPixelBuffer,
+        src: &mut $crate::buf::WuffsBuf,
+        blend: $crate::swizzler::WuffsPixelBlend,
+      ) -> Result<(), $crate::status::WuffsError> {
+        use $crate::status::IntoResult;
+
+        let needed = self.workbuf_len();
+        let work =
+          $crate::slice::WuffsSlice::from(Self::work_buf(&mut self.work, needed)?);
+
+        unsafe {
+          ::wuffs_sys::$decode_frame(
+            self.inner.as_mut_ptr(),
+            dst.as_mut_ptr(),
+            src.as_mut_ptr(),
+            blend.repr() as _,
+            work.into_inner(),
+            ::core::ptr::null_mut(),
+          )
+          .into_result()
+        }
+      }
+
+      fn num_animation_loops(&self) -> u32 {
+        unsafe { ::wuffs_sys::$num_animation_loops(self.inner.as_ptr()) }
+      }
+
+      fn num_decoded_frame_configs(&self) -> u64 {
+        unsafe { ::wuffs_sys::$num_decoded_frame_configs(self.inner.as_ptr()) }
+      }
+
+      fn num_decoded_frames(&self) -> u64 {
+        unsafe { ::wuffs_sys::$num_decoded_frames(self.inner.as_ptr()) }
+      }
+
+      fn workbuf_len(&self) -> usize {
+        unsafe { ::wuffs_sys::$workbuf_len(self.inner.as_ptr()).min_incl as _ }
+      }
+
+      fn frame_dirty_rect(&self) -> $crate::std::image::WuffsRect {
+        $crate::std::image::WuffsRect::from_inner(unsafe {
+          ::wuffs_sys::$frame_dirty_rect(self.inner.as_ptr())
+        })
+      }
+
+      fn restart_frame(
+        &mut self,
+        index: u64,
+        io_position: u64,
+      ) -> Result<(), $crate::status::WuffsError> {
+        use $crate::status::IntoResult;
+
+        unsafe {
+          ::wuffs_sys::$restart_frame(self.inner.as_mut_ptr(), index, io_position)
+            .into_result()
+        }
+      }
+    }
+
+    impl<B, W> $decoder<B, W>
+    where
+      B: $crate::boxed::WuffsStorage<$state> + Clone,
+      W: $work + Clone,
+    {
+      /// Snapshot the decoder so that both copies can carry on decoding independently
+      /// from the current position.
+      pub fn fork(&self) -> Self {
+        Self {
+          work: self.work.clone(),
+          inner: self.inner.clone(),
+          limits: self.limits,
+        }
+      }
+    }
+
+    impl<B, W> Clone for $decoder<B, W>
+    where
+      B: $crate::boxed::WuffsStorage<$state> + Clone,
+      W: $work + Clone,
+    {
+      fn clone(&self) -> Self {
+        self.fork()
+      }
+    }
+
+    impl $crate::boxed::WuffsBoxed for $state {
+      fn size() -> usize {
+        unsafe { ::wuffs_sys::$sizeof() as _ }
+      }
+    }
+
+    unsafe impl $crate::boxed::WuffsSized for $state {}
+  };
+}
@@ -0,0 +1,268 @@
+use alloc::{
+  string::{String, ToString},
+  vec::Vec,
+};
+
+use super::{
+  WuffsFrameConfig, WuffsImageConfig, WuffsImageDecoder, WuffsMoreInformation,
+  WuffsMoreInformationFlavor,
+};
+use crate::{
+  buf::WuffsBuf,
+  status::{WuffsError, WuffsNote, WuffsSuspension},
+};
+use wuffs_sys::{
+  WUFFS_BASE__FOURCC__CHRM, WUFFS_BASE__FOURCC__EXIF, WUFFS_BASE__FOURCC__GAMA,
+  WUFFS_BASE__FOURCC__ICCP, WUFFS_BASE__FOURCC__KVP, WUFFS_BASE__FOURCC__KVPK,
+  WUFFS_BASE__FOURCC__KVPV, WUFFS_BASE__FOURCC__SRGB, WUFFS_BASE__FOURCC__XMP,
+};
+
+/// Text key under which PNG stores XMP in an iTXt chunk.
+const XMP_KEY: &str = "XML:com.adobe.xmp";
+
+/// Size of the chunks converted metadata is written in.
+const CHUNK_LEN: usize = 4096;
+
+/// CIE 1931 xy chromaticities of the white point and primaries, scaled by 100000.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct WuffsChromaticities {
+  pub white: (i32, i32),
+  pub red: (i32, i32),
+  pub green: (i32, i32),
+  pub blue: (i32, i32),
+}
+
+/// Rendering intent of an sRGB image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WuffsRenderingIntent {
+  Perceptual,
+  RelativeColorimetric,
+  Saturation,
+  AbsoluteColorimetric,
+}
+
+impl WuffsRenderingIntent {
+  pub fn from_repr(repr: u8) -> Self {
+    match repr & 3 {
+      0 => Self::Perceptual,
+      1 => Self::RelativeColorimetric,
+      2 => Self::Saturation,
+      _ => Self::AbsoluteColorimetric,
+    }
+  }
+}
+
+/// Color and descriptive metadata of an image, gathered as the decoder reports it.
+///
+/// Call [`enable`](Self::enable) on a fresh decoder, then decode configs through
+/// [`decode_image_config`](Self::decode_image_config) and
+/// [`decode_frame_config`](Self::decode_frame_config).  PNG reports metadata before its
+/// first frame and may report more after its last, GIF anywhere between frames.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WuffsMetadata {
+  /// ICC color profile, decompressed.
+  pub icc_profile: Option<Vec<u8>>,
+  /// EXIF, starting with the TIFF header.
+  pub exif: Option<Vec<u8>>,
+  /// XMP packet, from a GIF application extension or a PNG iTXt chunk.
+  pub xmp: Option<Vec<u8>>,
+  /// Inverse gamma scaled by 100000, e.g. 45455 for a gamma of 2.2.
+  pub gamma: Option<u32>,
+  pub chromaticities: Option<WuffsChromaticities>,
+  /// Set when the image declares itself sRGB.
+  pub srgb_intent: Option<WuffsRenderingIntent>,
+  /// Key-value pairs from PNG tEXt, zTXt and iTXt chunks, in file order.
+  pub text: Vec<(String, String)>,
+}
+
+impl WuffsMetadata {
+  /// Ask `decoder` to report every kind of metadata collected here.
+  pub fn enable<D: WuffsImageDecoder>(decoder: &mut D) {
+    for &fourcc in &[
+      WUFFS_BASE__FOURCC__CHRM,
+      WUFFS_BASE__FOURCC__EXIF,
+      WUFFS_BASE__FOURCC__GAMA,
+      WUFFS_BASE__FOURCC__ICCP,
+      WUFFS_BASE__FOURCC__KVP,
+      WUFFS_BASE__FOURCC__SRGB,
+      WUFFS_BASE__FOURCC__XMP,
+    ] {
+      decoder.set_report_metadata(fourcc, true);
+    }
+  }
+
+  /// [`WuffsImageDecoder::decode_image_config`], collecting metadata reported on the way.
+  pub fn decode_image_config<D: WuffsImageDecoder>(
+    &mut self,
+    decoder: &mut D,
+    src: &mut WuffsBuf,
+  ) -> Result<WuffsImageConfig, WuffsError> {
+    loop {
+      match decoder.decode_image_config(src) {
+        Err(WuffsError::Note(WuffsNote::MetadataReported)) => {
+          self.collect(decoder, src)?
+        }
+        result => return result,
+      }
+    }
+  }
+
+  /// [`WuffsImageDecoder::decode_frame_config`], collecting metadata reported on the way.
+  pub fn decode_frame_config<D: WuffsImageDecoder>(
+    &mut self,
+    decoder: &mut D,
+    src: &mut WuffsBuf,
+  ) -> Result<WuffsFrameConfig, WuffsError> {
+    loop {
+      match decoder.decode_frame_config(src) {
+        Err(WuffsError::Note(WuffsNote::MetadataReported)) => {
+          self.collect(decoder, src)?
+        }
+        result => return result,
+      }
+    }
+  }
+
+  /// Read the metadata `decoder` just reported with
+  /// [`WuffsNote::MetadataReported`].  Raw metadata must lie wholly within `src`.
+  pub fn collect<D: WuffsImageDecoder>(
+    &mut self,
+    decoder: &mut D,
+    src: &mut WuffsBuf,
+  ) -> Result<(), WuffsError> {
+    // Runs of bytes sharing a fourcc, so that a key is told apart from its value.
+    let mut segments: Vec<(u32, Vec<u8>)> = Vec::new();
+    let mut chunk = [0; CHUNK_LEN];
+
+    loop {
+      let mut minfo = WuffsMoreInformation::default();
+      let mut dst = WuffsBuf::from_slice(&mut chunk);
+      let status = decoder.tell_me_more(&mut dst, &mut minfo, src);
+
+      let bytes = match minfo.flavor() {
+        WuffsMoreInformationFlavor::MetadataRawPassthrough => {
+          let (min, max) = minfo.passthrough_range();
+          let at = (src.pos() + src.read()) as u64;
+          // SAFETY: `src` is borrowed from the caller, who keeps its slice alive.
+          let unread = unsafe { src.unread() };
+
+          if min < at || max < min || (max - at) as usize > unread.len() {
+            return Err(WuffsError::Suspension(WuffsSuspension::ShortRead));
+          }
+
+          let bytes = &unread[(min - at) as usize..(max - at) as usize];
+          let len = bytes.len();
+          let segment = segment(&mut segments, minfo.fourcc());
+          segment.extend_from_slice(bytes);
+          src.skip((min - at) as usize + len);
+
+          None
+        }
+        WuffsMoreInformationFlavor::MetadataRawTransform => Some(dst.written()),
+        WuffsMoreInformationFlavor::MetadataParsed => {
+          self.parse(&minfo);
+          None
+        }
+        _ => None,
+      };
+
+      if let Some(written) = bytes {
+        segment(&mut segments, minfo.fourcc()).extend_from_slice(&chunk[..written]);
+      }
+
+      match status {
+        Ok(()) => break,
+        Err(WuffsError::Suspension(WuffsSuspension::EvenMoreInformation))
+        | Err(WuffsError::Suspension(WuffsSuspension::ShortWrite)) => {}
+        Err(err) => return Err(err),
+      }
+    }
+
+    self.store(segments);
+
+    Ok(())
+  }
+
+  fn parse(&mut self, minfo: &WuffsMoreInformation) {
+    match minfo.fourcc() {
+      WUFFS_BASE__FOURCC__GAMA => self.gamma = Some(minfo.parsed_gamma()),
+      WUFFS_BASE__FOURCC__SRGB => {
+        self.srgb_intent = Some(WuffsRenderingIntent::from_repr(minfo.parsed_srgb()))
+      }
+      WUFFS_BASE__FOURCC__CHRM => {
+        let xy = |i| (minfo.parsed_chrm(i), minfo.parsed_chrm(i + 1));
+
+        self.chromaticities = Some(WuffsChromaticities {
+          white: xy(0),
+          red: xy(2),
+          green: xy(4),
+          blue: xy(6),
+        });
+      }
+      _ => {}
+    }
+  }
+
+  fn store(&mut self, segments: Vec<(u32, Vec<u8>)>) {
+    let mut key = None;
+
+    for (fourcc, bytes) in segments {
+      match fourcc {
+        WUFFS_BASE__FOURCC__ICCP => self.icc_profile = Some(bytes),
+        WUFFS_BASE__FOURCC__EXIF => self.exif = Some(bytes),
+        WUFFS_BASE__FOURCC__XMP => self.xmp = Some(bytes),
+        WUFFS_BASE__FOURCC__KVPK => {
+          key = Some(String::from_utf8_lossy(&bytes).to_string())
+        }
+        WUFFS_BASE__FOURCC__KVPV => {
+          let key = key.take().unwrap_or_default();
+
+          if key == XMP_KEY && self.xmp.is_none() {
+            self.xmp = Some(bytes.clone());
+          }
+
+          self
+            .text
+            .push((key, String::from_utf8_lossy(&bytes).to_string()));
+        }
+        _ => {}
+      }
+    }
+  }
+}
+
+/// Bytes of the last segment if it has `fourcc`, otherwise of a new one.
+fn segment(segments: &mut Vec<(u32, Vec<u8>)>, fourcc: u32) -> &mut Vec<u8> {
+  if segments.last().map(|(last, _)| *last) != Some(fourcc) {
+    segments.push((fourcc, Vec::new()));
+  }
+
+  &mut segments.last_mut().unwrap().1
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  #[cfg(feature = "png")]
+  fn test_png_metadata() {
+    use super::WuffsMetadata;
+    use crate::{
+      buf::WuffsBuf,
+      std::image::png::{tests::RED, WuffsPngDecoder},
+    };
+
+    let mut src = unsafe { WuffsBuf::from_slice_readonly(&RED) };
+    let mut png = WuffsPngDecoder::new().unwrap();
+    let mut metadata = WuffsMetadata::default();
+    WuffsMetadata::enable(&mut png);
+
+    let image = metadata.decode_image_config(&mut png, &mut src).unwrap();
+    assert_eq!(image.width(), 1);
+    metadata.decode_frame_config(&mut png, &mut src).unwrap();
+
+    assert_eq!(metadata.icc_profile.as_deref(), Some(&b"profile"[..]));
+    assert_eq!(metadata.gamma, Some(45455));
+    assert_eq!(metadata.text, [("Title".into(), "Hi".into())]);
+    assert_eq!(metadata.srgb_intent, None);
+  }
+}
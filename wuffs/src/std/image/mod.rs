@@ -0,0 +1,151 @@
+#[cfg(any(
+  feature = "bmp",
+  feature = "gif",
+  feature = "jpeg",
+  feature = "png",
+  feature = "qoi",
+  feature = "targa"
+))]
+#[macro_use]
+mod macros;
+
+#[cfg(feature = "alloc")]
+pub mod animation;
+#[cfg(feature = "bmp")]
+pub mod bmp;
+pub mod config;
+#[cfg(feature = "gif")]
+pub mod gif;
+#[cfg(feature = "image")]
+mod image_impl;
+#[cfg(feature = "jpeg")]
+pub mod jpeg;
+pub mod limits;
+#[cfg(feature = "alloc")]
+pub mod metadata;
+#[cfg(feature = "png")]
+pub mod png;
+pub mod probe;
+#[cfg(feature = "alloc")]
+pub mod progressive;
+#[cfg(feature = "qoi")]
+pub mod qoi;
+#[cfg(feature = "std")]
+pub mod seek;
+#[cfg(feature = "targa")]
+pub mod targa;
+
+#[cfg(feature = "alloc")]
+pub use animation::{WuffsFrame, WuffsFrames};
+pub use config::{
+  WuffsDisposal, WuffsFrameConfig, WuffsImageConfig, WuffsMoreInformation,
+  WuffsMoreInformationFlavor, WuffsRect,
+};
+#[cfg(feature = "image")]
+pub use image_impl::WuffsImageCrateDecoder;
+pub use limits::{WuffsLimit, WuffsLimits};
+#[cfg(feature = "alloc")]
+pub use metadata::{WuffsChromaticities, WuffsMetadata, WuffsRenderingIntent};
+pub use probe::{probe, WuffsImageFormat, WuffsImageInfo};
+#[cfg(feature = "alloc")]
+pub use progressive::{WuffsProgress, WuffsProgressiveDecoder};
+#[cfg(feature = "std")]
+pub use seek::WuffsSeekableFrames;
+
+use crate::{
+  buf::WuffsBuf,
+  pixel::{WuffsPixelBuffer, WuffsPixelConfig, WuffsPixelFormat},
+  status::WuffsError,
+  swizzler::WuffsPixelBlend,
+};
+
+/// Operations shared by the wuffs image decoders.
+///
+/// Call [`decode_image_config`](Self::decode_image_config) once, then alternate between
+/// [`decode_frame_config`](Self::decode_frame_config) and
+/// [`decode_frame`](Self::decode_frame) until the frame config reports
+/// [`WuffsNote::EndOfData`](crate::status::WuffsNote::EndOfData).
+///
+/// Metadata asked for with [`set_report_metadata`](Self::set_report_metadata) makes the
+/// config methods stop with
+/// [`WuffsNote::MetadataReported`](crate::status::WuffsNote::MetadataReported); read it
+/// with [`tell_me_more`](Self::tell_me_more), then call the config method again.
+pub trait WuffsImageDecoder {
+  /// Report the metadata `fourcc`, one of the `WUFFS_BASE__FOURCC__*` constants, or
+  /// stop reporting it.  Kinds the format doesn't carry are ignored.
+  fn set_report_metadata(&mut self, fourcc: u32, report: bool);
+
+  /// Describe the reported metadata in `minfo`, writing converted bytes to `dst`.
+  /// Returns [`WuffsSuspension::EvenMoreInformation`] while there is more of it to come.
+  ///
+  /// [`WuffsSuspension::EvenMoreInformation`]: crate::status::WuffsSuspension::EvenMoreInformation
+  fn tell_me_more(
+    &mut self,
+    dst: &mut WuffsBuf,
+    minfo: &mut WuffsMoreInformation,
+    src: &mut WuffsBuf,
+  ) -> Result<(), WuffsError>;
+
+  fn limits(&self) -> WuffsLimits;
+
+  /// Bound the images [`decode_image_config`](Self::decode_image_config) accepts.
+  fn set_limits(&mut self, limits: WuffsLimits);
+
+  /// Fails with [`WuffsError::LimitExceeded`] for an image over the decoder's limits.
+  fn decode_image_config(
+    &mut self,
+    src: &mut WuffsBuf,
+  ) -> Result<WuffsImageConfig, WuffsError>;
+
+  fn decode_frame_config(
+    &mut self,
+    src: &mut WuffsBuf,
+  ) -> Result<WuffsFrameConfig, WuffsError>;
+
+  /// Decode the pixels of the current frame into `dst`, within the frame's bounds.
+  fn decode_frame(
+    &mut self,
+    dst: &mut WuffsPixelBuffer,
+    src: &mut WuffsBuf,
+    blend: WuffsPixelBlend,
+  ) -> Result<(), WuffsError>;
+
+  /// Decode the current frame straight into a caller's framebuffer, such as shared
+  /// memory: `dst` holds rows of the image's size in `format`, starting `stride` bytes
+  /// apart.  Bytes between the end of one row and the start of the next are left alone.
+  fn decode_frame_into(
+    &mut self,
+    image: &WuffsImageConfig,
+    dst: &mut [u8],
+    stride: usize,
+    format: WuffsPixelFormat,
+    src: &mut WuffsBuf,
+    blend: WuffsPixelBlend,
+  ) -> Result<(), WuffsError> {
+    let config = WuffsPixelConfig::new(format, image.width(), image.height());
+    let mut pixbuf = WuffsPixelBuffer::from_slice_with_stride(config, dst, stride)?;
+
+    self.decode_frame(&mut pixbuf, src, blend)
+  }
+
+  /// Times an animation should be played, 0 meaning forever.  Formats that only state
+  /// it part way through the file may update it as frames are decoded.
+  fn num_animation_loops(&self) -> u32;
+
+  fn num_decoded_frame_configs(&self) -> u64;
+
+  fn num_decoded_frames(&self) -> u64;
+
+  /// Bytes of work buffer [`decode_frame`](Self::decode_frame) needs, known once the
+  /// image config is decoded.
+  fn workbuf_len(&self) -> usize;
+
+  /// Area of the current frame that has been decoded so far.
+  fn frame_dirty_rect(&self) -> WuffsRect;
+
+  /// Rewind to the frame `index` whose config starts at `io_position` in the source.
+  fn restart_frame(&mut self, index: u64, io_position: u64) -> Result<(), WuffsError>;
+}
+
+/// Decoder for a format that can hold more than one frame, such as GIF or APNG.
+pub trait WuffsAnimatedDecoder: WuffsImageDecoder {}
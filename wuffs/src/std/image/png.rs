@@ -0,0 +1,170 @@
+use super::{WuffsAnimatedDecoder, WuffsLimits};
+use crate::{
+  boxed::{WuffsBorrowed, WuffsDefault, WuffsHeapWork, WuffsStorage, WuffsWork},
+  status::{IntoResult, WuffsError},
+};
+use wuffs_sys::{wuffs_png__decoder, wuffs_png__decoder__initialize, WUFFS_VERSION};
+
+/// PNG and APNG decoder.
+///
+/// The work buffer holds the whole decompressed image, so its size is only known once
+/// the image config is decoded.  A heap work buffer grows to fit; a slice must already
+/// be large enough, see [`workbuf_len`](super::WuffsImageDecoder::workbuf_len).
+pub struct WuffsPngDecoder<B = WuffsDefault<wuffs_png__decoder>, W = WuffsHeapWork> {
+  work: W,
+  inner: B,
+  limits: WuffsLimits,
+}
+
+impl WuffsPngDecoder {
+  #[cfg(feature = "alloc")]
+  pub fn new() -> Result<Self, WuffsError> {
+    Self::with_storage(WuffsDefault::default())
+  }
+}
+
+impl<B: WuffsStorage<wuffs_png__decoder>> WuffsPngDecoder<B> {
+  /// Initialize a decoder whose state lives in `inner`, with a heap work buffer.
+  #[cfg(feature = "alloc")]
+  pub fn with_storage(inner: B) -> Result<Self, WuffsError> {
+    Self::with_work(inner, WuffsHeapWork::default())
+  }
+}
+
+impl<'a> WuffsPngDecoder<WuffsBorrowed<'a, wuffs_png__decoder>, &'a mut [u8]> {
+  /// Initialize a decoder without allocating: the state lives in `state`, which must
+  /// satisfy [`WuffsBorrowed::new`], and `work` is the work buffer.
+  pub fn from_buffers(
+    state: &'a mut [u8],
+    work: &'a mut [u8],
+  ) -> Result<Self, WuffsError> {
+    Self::with_work(WuffsBorrowed::new(state)?, work)
+  }
+}
+
+impl<B: WuffsStorage<wuffs_png__decoder>, W: WuffsWork> WuffsPngDecoder<B, W> {
+  /// Initialize a decoder whose state lives in `inner` and whose work buffer is `work`.
+  pub fn with_work(mut inner: B, work: W) -> Result<Self, WuffsError> {
+    unsafe {
+      wuffs_png__decoder__initialize(
+        inner.as_mut_ptr(),
+        inner.size() as _,
+        WUFFS_VERSION as _,
+        0,
+      )
+      .into_result()?;
+    }
+
+    Ok(Self {
+      work,
+      inner,
+      limits: WuffsLimits::default(),
+    })
+  }
+
+  /// Borrow `needed` bytes of work buffer, growing it if it can be.
+  fn work_buf(work: &mut W, needed: usize) -> Result<&mut [u8], WuffsError> {
+    work.reserve(needed)
+  }
+}
+
+image_decoder! {
+  WuffsPngDecoder<W: WuffsWork>, wuffs_png__decoder {
+    sizeof: sizeof__wuffs_png__decoder,
+    set_quirk_enabled: wuffs_png__decoder__set_quirk_enabled,
+    set_quirk: wuffs_png__decoder__set_quirk,
+    set_report_metadata: wuffs_png__decoder__set_report_metadata,
+    tell_me_more: wuffs_png__decoder__tell_me_more,
+    decode_image_config: wuffs_png__decoder__decode_image_config,
+    decode_frame_config: wuffs_png__decoder__decode_frame_config,
+    decode_frame: wuffs_png__decoder__decode_frame,
+    num_animation_loops: wuffs_png__decoder__num_animation_loops,
+    num_decoded_frame_configs: wuffs_png__decoder__num_decoded_frame_configs,
+    num_decoded_frames: wuffs_png__decoder__num_decoded_frames,
+    workbuf_len: wuffs_png__decoder__workbuf_len,
+    frame_dirty_rect: wuffs_png__decoder__frame_dirty_rect,
+    restart_frame: wuffs_png__decoder__restart_frame,
+  }
+}
+
+impl<B: WuffsStorage<wuffs_png__decoder>, W: WuffsWork> WuffsAnimatedDecoder
+  for WuffsPngDecoder<B, W>
+{
+}
+
+#[cfg(all(test, feature = "alloc"))]
+pub(crate) mod tests {
+  use super::WuffsPngDecoder;
+  use crate::{
+    buf::WuffsBuf,
+    pixel::{WuffsPixelBuffer, WuffsPixelConfig, WuffsPixelFormat},
+    std::image::WuffsImageDecoder,
+    swizzler::WuffsPixelBlend,
+  };
+
+  // RED is a 1x1 opaque red PNG carrying an ICC profile whose bytes are "profile", a
+  // gamma of 1/2.2 and a Title=Hi text chunk.
+  pub(crate) const RED: [u8; 138] = [
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, // 00..07 signature
+    0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00,
+    0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1f, 0x15, 0xc4,
+    0x89, // 08..20 IHDR 1x1 RGBA8
+    0x00, 0x00, 0x00, 0x14, 0x69, 0x43, 0x43, 0x50, 0x69, 0x63, 0x63, 0x00, 0x00, 0x78,
+    0xda, 0x2b, 0x28, 0xca, 0x4f, 0xcb, 0xcc, 0x49, 0x05, 0x00, 0x0b, 0xfe, 0x02, 0xf2,
+    0x43, 0x6c, 0xad, 0x74, // 21..40 iCCP "icc", zlib of "profile"
+    0x00, 0x00, 0x00, 0x04, 0x67, 0x41, 0x4d, 0x41, 0x00, 0x00, 0xb1, 0x8f, 0x0b, 0xfc,
+    0x61, 0x05, // 41..50 gAMA 45455
+    0x00, 0x00, 0x00, 0x08, 0x74, 0x45, 0x58, 0x74, 0x54, 0x69, 0x74, 0x6c, 0x65, 0x00,
+    0x48, 0x69, 0xff, 0x0f, 0xa7, 0x7d, // 51..64 tEXt Title=Hi
+    0x00, 0x00, 0x00, 0x0d, 0x49, 0x44, 0x41, 0x54, 0x78, 0xda, 0x63, 0xf8, 0xcf, 0xc0,
+    0xf0, 0x1f, 0x00, 0x05, 0x00, 0x01, 0xff, 0x56, 0xc7, 0x2f,
+    0x0d, // 65..7d IDAT opaque red
+    0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60,
+    0x82, // 7e..89 IEND
+  ];
+
+  #[test]
+  fn test_png_decode_frame() {
+    let mut src = unsafe { WuffsBuf::from_slice_readonly(&RED) };
+    let mut png = WuffsPngDecoder::new().unwrap();
+
+    let image = png.decode_image_config(&mut src).unwrap();
+    assert_eq!((image.width(), image.height()), (1, 1));
+
+    png.decode_frame_config(&mut src).unwrap();
+
+    let config = WuffsPixelConfig::new(WuffsPixelFormat::RgbaNonpremul, 1, 1);
+    let mut buf = [0; 4];
+    let mut pixbuf = WuffsPixelBuffer::from_slice(config, &mut buf).unwrap();
+    png
+      .decode_frame(&mut pixbuf, &mut src, WuffsPixelBlend::Src)
+      .unwrap();
+
+    assert_eq!(pixbuf.pixel(0, 0), Some(&[0xff, 0, 0, 0xff][..]));
+  }
+
+  #[test]
+  fn test_png_work_buffer_limit() {
+    use crate::{
+      status::WuffsError,
+      std::image::{WuffsLimit, WuffsLimits},
+    };
+
+    let mut src = unsafe { WuffsBuf::from_slice_readonly(&RED) };
+    let mut png = WuffsPngDecoder::new().unwrap();
+    // Room for the pixel, but not for the filter byte that starts each row of work.
+    png.set_limits(WuffsLimits {
+      max_bytes: Some(4),
+      ..Default::default()
+    });
+
+    assert_eq!(
+      png.decode_image_config(&mut src).unwrap_err(),
+      WuffsError::LimitExceeded {
+        limit: WuffsLimit::WorkBuffer,
+        value: 5,
+        max: 4,
+      }
+    );
+  }
+}
@@ -0,0 +1,348 @@
+#[cfg(feature = "bmp")]
+use super::bmp::WuffsBmpDecoder;
+#[cfg(feature = "gif")]
+use super::gif::WuffsGifDecoder;
+#[cfg(feature = "jpeg")]
+use super::jpeg::WuffsJpegDecoder;
+#[cfg(feature = "png")]
+use super::png::WuffsPngDecoder;
+#[cfg(feature = "qoi")]
+use super::qoi::WuffsQoiDecoder;
+#[cfg(feature = "targa")]
+use super::targa::WuffsTargaDecoder;
+#[cfg(any(
+  feature = "bmp",
+  feature = "gif",
+  feature = "jpeg",
+  feature = "png",
+  feature = "qoi",
+  feature = "targa"
+))]
+use super::{WuffsImageConfig, WuffsImageDecoder};
+#[cfg(any(
+  feature = "bmp",
+  feature = "gif",
+  feature = "jpeg",
+  feature = "png",
+  feature = "qoi",
+  feature = "targa"
+))]
+use crate::{boxed::WuffsInline, buf::WuffsBuf};
+use crate::{
+  pixel::WuffsPixelFormat,
+  status::{WuffsError, WuffsSuspension},
+};
+
+/// Image formats [`probe`] recognizes, each behind the feature of the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WuffsImageFormat {
+  #[cfg(feature = "bmp")]
+  Bmp,
+  #[cfg(feature = "gif")]
+  Gif,
+  #[cfg(feature = "jpeg")]
+  Jpeg,
+  #[cfg(feature = "png")]
+  Png,
+  #[cfg(feature = "qoi")]
+  Qoi,
+  /// TGA has no signature, so it is only guessed from its header when nothing else
+  /// matches.
+  #[cfg(feature = "targa")]
+  Targa,
+}
+
+impl WuffsImageFormat {
+  /// Identify the format from the leading bytes of a file.  Fails with
+  /// [`WuffsSuspension::ShortRead`] when `prefix` is too short to tell.
+  pub fn detect(prefix: &[u8]) -> Result<Self, WuffsError> {
+    let signatures: &[(&[u8], Self)] = &[
+      #[cfg(feature = "bmp")]
+      (b"BM", Self::Bmp),
+      #[cfg(feature = "gif")]
+      (b"GIF87a", Self::Gif),
+      #[cfg(feature = "gif")]
+      (b"GIF89a", Self::Gif),
+      #[cfg(feature = "jpeg")]
+      (b"\xff\xd8\xff", Self::Jpeg),
+      #[cfg(feature = "png")]
+      (b"\x89PNG\r\n\x1a\n", Self::Png),
+      #[cfg(feature = "qoi")]
+      (b"qoif", Self::Qoi),
+    ];
+    let mut short = false;
+
+    for &(signature, format) in signatures {
+      if prefix.starts_with(signature) {
+        return Ok(format);
+      }
+
+      short |= signature.starts_with(prefix);
+    }
+
+    #[cfg(feature = "targa")]
+    match targa_header(prefix) {
+      Some(true) => return Ok(Self::Targa),
+      None => short = true,
+      _ => {}
+    }
+
+    Err(if short {
+      WuffsError::Suspension(WuffsSuspension::ShortRead)
+    } else {
+      WuffsError::UnknownFormat
+    })
+  }
+}
+
+/// What [`probe`] learned about an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WuffsImageInfo {
+  pub format: WuffsImageFormat,
+  pub width: u32,
+  pub height: u32,
+  /// Pixel format the decoder would produce natively, if this crate models it.
+  pub pixel_format: Option<WuffsPixelFormat>,
+  /// Number of frames, when the header states it as APNG does.
+  pub frame_count_hint: Option<u32>,
+  /// The image declares animation, or a second frame was found within the input.
+  pub has_animation: bool,
+}
+
+/// Read the dimensions and format of the image in `src` without decoding or allocating
+/// any pixels.
+///
+/// `src` may be a prefix of the file: when it ends before the image config, this fails
+/// with [`WuffsSuspension::ShortRead`] and can be retried with more bytes.
+pub fn probe(src: &[u8]) -> Result<WuffsImageInfo, WuffsError> {
+  let format = WuffsImageFormat::detect(src)?;
+
+  #[cfg(any(
+    feature = "bmp",
+    feature = "gif",
+    feature = "jpeg",
+    feature = "png",
+    feature = "qoi",
+    feature = "targa"
+  ))]
+  {
+    let mut buf = unsafe { WuffsBuf::from_slice_readonly(src) };
+    buf.set_closed(false);
+
+    let (image, frame_count_hint, has_animation) = match format {
+      #[cfg(feature = "bmp")]
+      WuffsImageFormat::Bmp => (
+        WuffsBmpDecoder::with_storage(WuffsInline::new())?
+          .decode_image_config(&mut buf)?,
+        Some(1),
+        false,
+      ),
+      #[cfg(feature = "gif")]
+      WuffsImageFormat::Gif => {
+        let mut gif = WuffsGifDecoder::with_storage(WuffsInline::new())?;
+        let image = gif.decode_image_config(&mut buf)?;
+
+        (image, None, has_second_frame(&mut gif, &mut buf))
+      }
+      #[cfg(feature = "jpeg")]
+      WuffsImageFormat::Jpeg => (
+        WuffsJpegDecoder::with_work(WuffsInline::new(), &mut [][..])?
+          .decode_image_config(&mut buf)?,
+        Some(1),
+        false,
+      ),
+      #[cfg(feature = "png")]
+      WuffsImageFormat::Png => {
+        // The work buffer is only needed for pixels.
+        let image = WuffsPngDecoder::with_work(WuffsInline::new(), &mut [][..])?
+          .decode_image_config(&mut buf)?;
+        let frames = apng_frame_count(src);
+
+        (image, frames, matches!(frames, Some(frames) if frames > 1))
+      }
+      #[cfg(feature = "qoi")]
+      WuffsImageFormat::Qoi => (
+        WuffsQoiDecoder::with_work(WuffsInline::new(), &mut [][..])?
+          .decode_image_config(&mut buf)?,
+        Some(1),
+        false,
+      ),
+      #[cfg(feature = "targa")]
+      WuffsImageFormat::Targa => (
+        WuffsTargaDecoder::with_work(WuffsInline::new(), &mut [][..])?
+          .decode_image_config(&mut buf)?,
+        Some(1),
+        false,
+      ),
+    };
+
+    Ok(info(format, image, frame_count_hint, has_animation))
+  }
+
+  #[cfg(not(any(
+    feature = "bmp",
+    feature = "gif",
+    feature = "jpeg",
+    feature = "png",
+    feature = "qoi",
+    feature = "targa"
+  )))]
+  match format {}
+}
+
+#[cfg(any(
+  feature = "bmp",
+  feature = "gif",
+  feature = "jpeg",
+  feature = "png",
+  feature = "qoi",
+  feature = "targa"
+))]
+fn info(
+  format: WuffsImageFormat,
+  image: WuffsImageConfig,
+  frame_count_hint: Option<u32>,
+  has_animation: bool,
+) -> WuffsImageInfo {
+  WuffsImageInfo {
+    format,
+    width: image.width(),
+    height: image.height(),
+    pixel_format: image.pixel_config().format(),
+    frame_count_hint,
+    has_animation,
+  }
+}
+
+/// Skip the first frame's pixels to look for a second frame config.  Frame configs
+/// carry no pixels, so this needs no pixel buffer.
+#[cfg(feature = "gif")]
+fn has_second_frame<D: WuffsImageDecoder>(decoder: &mut D, src: &mut WuffsBuf) -> bool {
+  (0..2).all(|_| decoder.decode_frame_config(src).is_ok())
+}
+
+/// Whether `prefix` looks like a TGA header: a color map type of 0 or 1, a known image
+/// type and a common pixel depth.  `None` when it is too short to tell.
+#[cfg(feature = "targa")]
+fn targa_header(prefix: &[u8]) -> Option<bool> {
+  // Bytes past the end of `prefix` may still turn out valid.
+  let valid =
+    |at: usize, values: &[u8]| prefix.get(at).iter().all(|b| values.contains(b));
+  let plausible = valid(1, &[0, 1])
+    && valid(2, &[1, 2, 3, 9, 10, 11])
+    && valid(16, &[8, 15, 16, 24, 32]);
+
+  match plausible {
+    true if prefix.len() < 18 => None,
+    plausible => Some(plausible),
+  }
+}
+
+/// `num_frames` of the APNG acTL chunk, which must come before the first IDAT.
+#[cfg(feature = "png")]
+fn apng_frame_count(src: &[u8]) -> Option<u32> {
+  let mut chunks = src.get(8..)?;
+
+  while chunks.len() >= 12 {
+    let len = u32::from_be_bytes([chunks[0], chunks[1], chunks[2], chunks[3]]) as usize;
+
+    match &chunks[4..8] {
+      b"acTL" => {
+        let data = chunks.get(8..12)?;
+        return Some(u32::from_be_bytes([data[0], data[1], data[2], data[3]]));
+      }
+      b"IDAT" => return None,
+      _ => chunks = 12usize.checked_add(len).and_then(|end| chunks.get(end..))?,
+    }
+  }
+
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{probe, WuffsImageFormat};
+  use crate::status::{WuffsError, WuffsSuspension};
+
+  #[test]
+  fn test_detect() {
+    assert_eq!(
+      WuffsImageFormat::detect(b"\x89P"),
+      Err(WuffsError::Suspension(WuffsSuspension::ShortRead))
+    );
+    assert_eq!(probe(b"RIFF"), Err(WuffsError::UnknownFormat));
+  }
+
+  #[test]
+  #[cfg(all(feature = "gif", feature = "alloc"))]
+  fn test_probe_gif() {
+    use crate::std::image::gif::tests::ANIMATED;
+
+    let info = probe(&ANIMATED).unwrap();
+    assert_eq!(info.format, WuffsImageFormat::Gif);
+    assert_eq!((info.width, info.height), (2, 2));
+    assert!(info.has_animation);
+
+    // Everything up to the first frame's pixels is enough for the dimensions.
+    let info = probe(&ANIMATED[..0x3e]).unwrap();
+    assert_eq!((info.width, info.height), (2, 2));
+    assert!(!info.has_animation);
+
+    assert_eq!(
+      probe(&ANIMATED[..0x0a]),
+      Err(WuffsError::Suspension(WuffsSuspension::ShortRead))
+    );
+  }
+
+  #[test]
+  #[cfg(all(feature = "png", feature = "alloc"))]
+  fn test_probe_png() {
+    use crate::std::image::png::tests::RED;
+
+    let info = probe(&RED[..0x6d]).unwrap();
+    assert_eq!(info.format, WuffsImageFormat::Png);
+    assert_eq!((info.width, info.height), (1, 1));
+    assert_eq!(info.frame_count_hint, None);
+    assert!(!info.has_animation);
+  }
+
+  #[test]
+  #[cfg(all(feature = "jpeg", feature = "alloc"))]
+  fn test_probe_jpeg() {
+    use crate::std::image::jpeg::tests::GRAY;
+
+    let info = probe(&GRAY).unwrap();
+    assert_eq!(info.format, WuffsImageFormat::Jpeg);
+    assert_eq!((info.width, info.height), (8, 8));
+  }
+
+  #[test]
+  #[cfg(all(feature = "qoi", feature = "alloc"))]
+  fn test_probe_qoi() {
+    use crate::std::image::qoi::tests::RED;
+
+    let info = probe(&RED).unwrap();
+    assert_eq!(info.format, WuffsImageFormat::Qoi);
+    assert_eq!((info.width, info.height), (1, 1));
+  }
+
+  #[test]
+  #[cfg(all(feature = "targa", feature = "alloc"))]
+  fn test_probe_targa() {
+    use crate::std::image::targa::tests::RED;
+
+    let info = probe(&RED).unwrap();
+    assert_eq!(info.format, WuffsImageFormat::Targa);
+    assert_eq!((info.width, info.height), (1, 1));
+
+    // A header cut short of its pixel depth could still be TGA.
+    assert_eq!(
+      WuffsImageFormat::detect(&RED[..16]),
+      Err(WuffsError::Suspension(WuffsSuspension::ShortRead))
+    );
+    assert_eq!(
+      WuffsImageFormat::detect(b"\x00\x07\x02"),
+      Err(WuffsError::UnknownFormat)
+    );
+  }
+}
@@ -0,0 +1,341 @@
+use alloc::vec::Vec;
+
+use super::{
+  animation::Canvas, WuffsFrameConfig, WuffsImageConfig, WuffsImageDecoder,
+  WuffsMoreInformation, WuffsMoreInformationFlavor, WuffsRect,
+};
+use crate::{
+  buf::WuffsBuf,
+  pixel::WuffsPixelBuffer,
+  status::{WuffsError, WuffsNote, WuffsSuspension},
+  swizzler::WuffsPixelBlend,
+};
+
+/// What changed after a [`WuffsProgressiveDecoder::push`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WuffsProgress {
+  /// Area of the canvas changed by this push, empty when nothing was drawn.  Parts of
+  /// a frame drawn by earlier pushes are left out.
+  pub dirty_rect: WuffsRect,
+  /// Frames decoded in full so far.
+  pub frames: u64,
+  /// The image has ended, or decoding failed; further input is ignored.
+  pub done: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+  ImageConfig,
+  FrameConfig,
+  Frame(WuffsPixelBlend),
+  Done,
+}
+
+/// Decoder fed with input as it arrives, rendering onto a non-premultiplied RGBA canvas
+/// as far as the bytes so far allow.
+///
+/// wuffs decoders suspend with [`WuffsSuspension::ShortRead`] when they run out of input
+/// and pick up where they left off once there is more.  GIF draws a partly received
+/// frame up to its last decoded pixel, while PNG only filters its rows once the whole
+/// frame has been decompressed, so a PNG frame shows up when it is complete.  Animation
+/// frames are composited as [`WuffsFrames`](super::WuffsFrames) does.  Consumed input
+/// is dropped once it outweighs the bytes the decoder hasn't reached yet.
+///
+/// Metadata the decoder was asked to report is skipped, decode with
+/// [`WuffsMetadata`](super::WuffsMetadata) to read it.
+pub struct WuffsProgressiveDecoder<D> {
+  decoder: D,
+  /// Input received, of which the decoder consumed the first `consumed` bytes.
+  input: Vec<u8>,
+  consumed: usize,
+  /// Stream position of the first byte not consumed yet.
+  pos: u64,
+  closed: bool,
+  step: Step,
+  /// The decoder reported metadata and waits for it to be read.
+  reporting: bool,
+  /// Stream position up to which input is metadata to drop.
+  skip_to: u64,
+  image: Option<WuffsImageConfig>,
+  frame: Option<WuffsFrameConfig>,
+  /// Area of the current frame that earlier pushes already reported.
+  frame_dirty: WuffsRect,
+  canvas: Option<Canvas>,
+}
+
+impl<D: WuffsImageDecoder> WuffsProgressiveDecoder<D> {
+  /// Start decoding with a freshly initialized `decoder`, whose
+  /// [limits](WuffsImageDecoder::set_limits) also bound the canvas.
+  pub fn new(decoder: D) -> Self {
+    Self {
+      decoder,
+      input: Vec::new(),
+      consumed: 0,
+      pos: 0,
+      closed: false,
+      step: Step::ImageConfig,
+      reporting: false,
+      skip_to: 0,
+      image: None,
+      frame: None,
+      frame_dirty: WuffsRect::default(),
+      canvas: None,
+    }
+  }
+
+  /// Append the next `chunk` of input and decode as much as it allows.
+  pub fn push(&mut self, chunk: &[u8]) -> Result<WuffsProgress, WuffsError> {
+    if self.step != Step::Done {
+      // Moving the unconsumed bytes down only once they are fewer than the consumed
+      // ones keeps the copying linear in the input.
+      if self.consumed > self.input.len() / 2 {
+        self.input.drain(..self.consumed);
+        self.consumed = 0;
+      }
+
+      self.input.extend_from_slice(chunk);
+    }
+
+    self.advance()
+  }
+
+  /// Signal the end of input.  Fails if the image was cut short.
+  pub fn finish(&mut self) -> Result<WuffsProgress, WuffsError> {
+    self.closed = true;
+
+    self.advance()
+  }
+
+  /// Image config, once enough input has arrived to decode it.
+  pub fn image_config(&self) -> Option<WuffsImageConfig> {
+    self.image
+  }
+
+  /// Config of the frame being decoded or, between frames, the last one drawn.
+  pub fn frame_config(&self) -> Option<WuffsFrameConfig> {
+    self.frame
+  }
+
+  /// Pixels decoded so far, once the image config is known.
+  pub fn canvas(&self) -> Option<&WuffsPixelBuffer<'static>> {
+    self.canvas.as_ref().map(Canvas::pixbuf)
+  }
+
+  pub fn decoder(&self) -> &D {
+    &self.decoder
+  }
+
+  pub fn into_decoder(self) -> D {
+    self.decoder
+  }
+
+  fn advance(&mut self) -> Result<WuffsProgress, WuffsError> {
+    let mut dirty = WuffsRect::default();
+
+    while self.step != Step::Done {
+      let mut src =
+        unsafe { WuffsBuf::from_slice_readonly(&self.input[self.consumed..]) };
+      src.set_pos(self.pos);
+      src.set_closed(self.closed);
+
+      let result = self.step(&mut src, &mut dirty);
+
+      let read = src.read();
+      self.consumed += read;
+      self.pos += read as u64;
+
+      match result {
+        Ok(()) => {}
+        Err(WuffsError::Suspension(WuffsSuspension::ShortRead)) if !self.closed => break,
+        Err(err) => {
+          self.step = Step::Done;
+          return Err(err);
+        }
+      }
+    }
+
+    Ok(WuffsProgress {
+      dirty_rect: dirty,
+      frames: self.decoder.num_decoded_frames(),
+      done: self.step == Step::Done,
+    })
+  }
+
+  fn step(
+    &mut self,
+    src: &mut WuffsBuf,
+    dirty: &mut WuffsRect,
+  ) -> Result<(), WuffsError> {
+    if self.reporting {
+      self.skip_metadata(src)?;
+    }
+
+    self.skip_input(src)?;
+
+    match self.step {
+      Step::ImageConfig => match self.decoder.decode_image_config(src) {
+        Ok(image) => {
+          self.canvas = Some(Canvas::new(&self.decoder, &image)?);
+          self.image = Some(image);
+          self.step = Step::FrameConfig;
+        }
+        Err(WuffsError::Note(WuffsNote::MetadataReported)) => self.reporting = true,
+        Err(err) => return Err(err),
+      },
+      Step::FrameConfig => match self.decoder.decode_frame_config(src) {
+        Ok(frame) => {
+          let canvas = self.canvas.as_mut().expect("image config decoded");
+          let (blend, changed) = canvas.begin(&frame)?;
+
+          *dirty = dirty.union(&changed);
+          self.frame = Some(frame);
+          self.frame_dirty = WuffsRect::default();
+          self.step = Step::Frame(blend);
+        }
+        Err(WuffsError::Note(WuffsNote::EndOfData)) => self.step = Step::Done,
+        Err(WuffsError::Note(WuffsNote::MetadataReported)) => self.reporting = true,
+        Err(err) => return Err(err),
+      },
+      Step::Frame(blend) => {
+        let canvas = self.canvas.as_mut().expect("image config decoded");
+        let result = self.decoder.decode_frame(canvas.pixbuf_mut(), src, blend);
+
+        let frame_dirty = self.decoder.frame_dirty_rect();
+        *dirty = dirty.union(&frame_dirty.difference(&self.frame_dirty));
+        self.frame_dirty = frame_dirty;
+        result?;
+
+        canvas.finish(self.frame.expect("frame config decoded"));
+        self.step = Step::FrameConfig;
+      }
+      Step::Done => {}
+    }
+
+    Ok(())
+  }
+
+  /// Let the decoder describe the metadata it reported, throwing away what it writes
+  /// and noting any source bytes that hold the metadata as they are.
+  fn skip_metadata(&mut self, src: &mut WuffsBuf) -> Result<(), WuffsError> {
+    let mut scratch = [0; 256];
+
+    loop {
+      let mut minfo = WuffsMoreInformation::default();
+      let mut dst = WuffsBuf::from_slice(&mut scratch);
+      let status = self.decoder.tell_me_more(&mut dst, &mut minfo, src);
+
+      if let WuffsMoreInformationFlavor::MetadataRawPassthrough = minfo.flavor() {
+        self.skip_to = minfo.passthrough_range().1;
+      }
+
+      match status {
+        Ok(()) => {
+          self.reporting = false;
+          return Ok(());
+        }
+        Err(WuffsError::Suspension(WuffsSuspension::EvenMoreInformation))
+        | Err(WuffsError::Suspension(WuffsSuspension::ShortWrite)) => {}
+        Err(err) => return Err(err),
+      }
+    }
+  }
+
+  /// Drop input up to `skip_to`, as far as it has arrived.
+  fn skip_input(&mut self, src: &mut WuffsBuf) -> Result<(), WuffsError> {
+    let at = (src.pos() + src.read()) as u64;
+
+    if at < self.skip_to {
+      let unread = (src.written() - src.read()) as u64;
+      src.skip((self.skip_to - at).min(unread) as usize);
+
+      if self.skip_to - at > unread {
+        return Err(WuffsError::Suspension(WuffsSuspension::ShortRead));
+      }
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  #[cfg(feature = "gif")]
+  fn test_progressive_gif() {
+    use super::WuffsProgressiveDecoder;
+    use crate::std::image::{gif::tests::ANIMATED, gif::WuffsGifDecoder, WuffsRect};
+
+    let mut decoder = WuffsProgressiveDecoder::new(WuffsGifDecoder::new().unwrap());
+
+    // Up to the first frame's pixels: the canvas exists and is cleared.
+    let progress = decoder.push(&ANIMATED[..0x3e]).unwrap();
+    assert_eq!(progress.frames, 0);
+    assert!(!progress.done);
+    assert_eq!(progress.dirty_rect.width(), 2);
+    assert_eq!(decoder.image_config().unwrap().width(), 2);
+
+    // Part of the first frame's pixels, then nothing new.
+    decoder.push(&ANIMATED[0x3e..0x40]).unwrap();
+    assert!(decoder.push(&[]).unwrap().dirty_rect.is_empty());
+
+    // The rest of the first frame.
+    let progress = decoder.push(&ANIMATED[0x40..0x43]).unwrap();
+    assert_eq!(progress.frames, 1);
+    assert_eq!(
+      decoder.canvas().unwrap().pixel(1, 1),
+      Some(&[0xff, 0, 0, 0xff][..])
+    );
+
+    let progress = decoder.push(&ANIMATED[0x43..]).unwrap();
+    assert_eq!(progress.frames, 2);
+    assert_eq!(
+      progress.dirty_rect,
+      WuffsRect {
+        min_x: 1,
+        min_y: 1,
+        max_x: 2,
+        max_y: 2,
+      }
+    );
+    assert!(progress.done);
+  }
+
+  #[test]
+  #[cfg(feature = "png")]
+  fn test_progressive_png_metadata() {
+    use super::WuffsProgressiveDecoder;
+    use crate::std::image::{
+      png::{tests::RED, WuffsPngDecoder},
+      WuffsMetadata,
+    };
+
+    let mut png = WuffsPngDecoder::new().unwrap();
+    WuffsMetadata::enable(&mut png);
+    let mut decoder = WuffsProgressiveDecoder::new(png);
+
+    // Reported metadata is skipped however the input is split.
+    for byte in RED.chunks(1) {
+      decoder.push(byte).unwrap();
+    }
+
+    let progress = decoder.finish().unwrap();
+    assert_eq!(progress.frames, 1);
+    assert!(progress.done);
+    assert_eq!(
+      decoder.canvas().unwrap().pixel(0, 0),
+      Some(&[0xff, 0, 0, 0xff][..])
+    );
+  }
+
+  #[test]
+  #[cfg(feature = "gif")]
+  fn test_progressive_truncated() {
+    use super::WuffsProgressiveDecoder;
+    use crate::std::image::gif::{tests::ANIMATED, WuffsGifDecoder};
+
+    let mut decoder = WuffsProgressiveDecoder::new(WuffsGifDecoder::new().unwrap());
+    decoder.push(&ANIMATED[..0x40]).unwrap();
+
+    assert!(decoder.finish().is_err());
+  }
+}
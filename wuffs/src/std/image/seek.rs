@@ -0,0 +1,279 @@
+use std::{
+  io::{self, Read, Seek, SeekFrom},
+  vec::Vec,
+};
+
+use super::{
+  animation::Canvas, WuffsDisposal, WuffsFrame, WuffsFrameConfig, WuffsImageConfig,
+  WuffsImageDecoder,
+};
+use crate::{
+  buf::WuffsBuf,
+  status::{WuffsError, WuffsNote, WuffsSuspension},
+};
+
+/// Bytes read from the source at a time.
+const CHUNK_LEN: usize = 64 * 1024;
+
+/// Random access to the frames of an animation read from a [`Read`] + [`Seek`] source.
+///
+/// The first pass records where every frame config starts.  After that, frame `n` is
+/// drawn by seeking back to the closest frame at or before it that doesn't depend on
+/// the frames before it, and compositing forward from there as
+/// [`WuffsFrames`](super::WuffsFrames) does.  Asking for frames in order carries on
+/// from the last one without seeking.
+pub struct WuffsSeekableFrames<R, D> {
+  decoder: D,
+  source: Source<R>,
+  image: WuffsImageConfig,
+  canvas: Canvas,
+  /// Config of every frame, once the first pass is done.
+  frames: Option<Vec<WuffsFrameConfig>>,
+  /// Index of the frame the decoder is positioned at, with every earlier one from the
+  /// last seek drawn on the canvas.
+  next: u64,
+}
+
+impl<R: Read + Seek, D: WuffsImageDecoder> WuffsSeekableFrames<R, D> {
+  /// Decode the image config from the start of `reader` with a freshly initialized
+  /// `decoder`, whose [limits](WuffsImageDecoder::set_limits) also bound the canvas.
+  pub fn new(mut decoder: D, reader: R) -> io::Result<Self> {
+    let mut source = Source::new(reader)?;
+    let image = source.call(|src| decoder.decode_image_config(src))??;
+
+    Ok(Self {
+      canvas: Canvas::new(&decoder, &image)?,
+      decoder,
+      source,
+      image,
+      frames: None,
+      next: 0,
+    })
+  }
+
+  pub fn image_config(&self) -> WuffsImageConfig {
+    self.image
+  }
+
+  /// Config of every frame, including where each starts in the source.  The first
+  /// call reads through the whole source.
+  pub fn frame_configs(&mut self) -> io::Result<&[WuffsFrameConfig]> {
+    if self.frames.is_none() {
+      self.frames = Some(self.scan()?);
+    }
+
+    Ok(self.frames.as_deref().unwrap_or_default())
+  }
+
+  /// Draw frame `n` with every earlier frame composited underneath, or `None` past the
+  /// last frame.
+  pub fn frame(&mut self, n: u64) -> io::Result<Option<WuffsFrame>> {
+    let key = match self.frame_configs()?.get(n as usize) {
+      Some(_) => self.keyframe(n),
+      None => return Ok(None),
+    };
+
+    // Carry on from where the decoder is, unless a later starting point exists.
+    if !(key < self.next && self.next <= n) {
+      self.restart(key)?;
+    }
+
+    let decoder = &mut self.decoder;
+    let canvas = &mut self.canvas;
+    let mut next = self.next;
+    let mut drawn = None;
+
+    // An error part way leaves the canvas out of step, so the next call must restart.
+    self.next = u64::MAX;
+
+    while next <= n {
+      let frame = self.source.call(|src| decoder.decode_frame_config(src))??;
+      let (blend, _) = canvas.begin(&frame)?;
+
+      self
+        .source
+        .call(|src| decoder.decode_frame(canvas.pixbuf_mut(), src, blend))??;
+      canvas.finish(frame);
+
+      drawn = Some(frame);
+      next += 1;
+    }
+
+    self.next = next;
+
+    Ok(drawn.map(|frame| self.canvas.frame(&frame)))
+  }
+
+  /// The decoder, e.g. for [`num_animation_loops`](WuffsImageDecoder::num_animation_loops).
+  pub fn decoder(&self) -> &D {
+    &self.decoder
+  }
+
+  /// Give back the reader, positioned wherever the last read left it.
+  pub fn into_reader(self) -> R {
+    self.source.reader
+  }
+
+  /// Read every frame config from the first, skipping their pixels.
+  fn scan(&mut self) -> io::Result<Vec<WuffsFrameConfig>> {
+    let decoder = &mut self.decoder;
+    let first = self.image.first_frame_io_position();
+    let mut frames = Vec::new();
+
+    decoder.restart_frame(0, first)?;
+    self.source.seek(first)?;
+    // The canvas no longer matches where the decoder is.
+    self.next = u64::MAX;
+
+    loop {
+      match self.source.call(|src| decoder.decode_frame_config(src))? {
+        Ok(frame) => frames.push(frame),
+        Err(WuffsError::Note(WuffsNote::EndOfData)) => return Ok(frames),
+        Err(err) => return Err(err.into()),
+      }
+    }
+  }
+
+  /// Index of the closest frame at or before `n` that can be drawn without the ones
+  /// before it: the first, or one that overwrites the whole canvas.
+  fn keyframe(&self, n: u64) -> u64 {
+    let frames = self.frames.as_deref().unwrap_or_default();
+    let (width, height) = (self.image.width(), self.image.height());
+
+    (1..=n)
+      .rev()
+      .find(|&i| {
+        let frame = &frames[i as usize];
+        let bounds = frame.bounds();
+
+        bounds.min_x == 0
+          && bounds.min_y == 0
+          && bounds.max_x >= width
+          && bounds.max_y >= height
+          && (frame.opaque_within_bounds() || frame.overwrite_instead_of_blend())
+          // Restoring after the frame would need the canvas from before it.
+          && frame.disposal() != WuffsDisposal::RestorePrevious
+      })
+      .unwrap_or(0)
+  }
+
+  fn restart(&mut self, index: u64) -> io::Result<()> {
+    let position = match index {
+      0 => self.image.first_frame_io_position(),
+      _ => self.frames.as_deref().unwrap_or_default()[index as usize].io_position(),
+    };
+
+    self.decoder.restart_frame(index, position)?;
+    self.source.seek(position)?;
+    self.canvas.reset();
+    self.next = index;
+
+    Ok(())
+  }
+}
+
+/// Window of the reader that the decoder hasn't consumed yet.
+struct Source<R> {
+  reader: R,
+  input: Vec<u8>,
+  /// Position in the reader of the first byte of `input`.
+  pos: u64,
+  eof: bool,
+}
+
+impl<R: Read + Seek> Source<R> {
+  fn new(reader: R) -> io::Result<Self> {
+    let mut source = Self {
+      reader,
+      input: Vec::new(),
+      pos: 0,
+      eof: false,
+    };
+    source.seek(0)?;
+
+    Ok(source)
+  }
+
+  /// Call `f` with the window, reading more each time it runs short.  I/O errors are
+  /// kept apart from the decoder's.
+  fn call<T>(
+    &mut self,
+    mut f: impl FnMut(&mut WuffsBuf) -> Result<T, WuffsError>,
+  ) -> io::Result<Result<T, WuffsError>> {
+    loop {
+      let mut src = unsafe { WuffsBuf::from_slice_readonly(&self.input) };
+      src.set_pos(self.pos);
+      src.set_closed(self.eof);
+
+      let result = f(&mut src);
+
+      let read = src.read();
+      self.input.drain(..read);
+      self.pos += read as u64;
+
+      match result {
+        Err(WuffsError::Suspension(WuffsSuspension::ShortRead)) if !self.eof => {
+          self.fill()?
+        }
+        result => return Ok(result),
+      }
+    }
+  }
+
+  fn fill(&mut self) -> io::Result<()> {
+    let len = self.input.len();
+    self.input.resize(len + CHUNK_LEN, 0);
+
+    let read = loop {
+      match self.reader.read(&mut self.input[len..]) {
+        Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+        result => break result,
+      }
+    };
+
+    self.input.truncate(len + *read.as_ref().unwrap_or(&0));
+    self.eof = read? == 0;
+
+    Ok(())
+  }
+
+  fn seek(&mut self, pos: u64) -> io::Result<()> {
+    self.reader.seek(SeekFrom::Start(pos))?;
+    self.input.clear();
+    self.pos = pos;
+    self.eof = false;
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  #[cfg(feature = "gif")]
+  fn test_seek_gif_frames() {
+    use super::WuffsSeekableFrames;
+    use crate::std::image::gif::{tests::ANIMATED, WuffsGifDecoder};
+    use std::io::Cursor;
+
+    const RED: [u8; 4] = [0xff, 0, 0, 0xff];
+    const GREEN: [u8; 4] = [0, 0xff, 0, 0xff];
+
+    let mut frames =
+      WuffsSeekableFrames::new(WuffsGifDecoder::new().unwrap(), Cursor::new(&ANIMATED))
+        .unwrap();
+
+    let configs = frames.frame_configs().unwrap();
+    assert_eq!(configs.len(), 2);
+    assert_eq!(configs[1].index(), 1);
+    assert!(configs[1].io_position() > configs[0].io_position());
+
+    let second = frames.frame(1).unwrap().unwrap();
+    assert_eq!(second.pixels, [RED, RED, RED, GREEN].concat());
+
+    let first = frames.frame(0).unwrap().unwrap();
+    assert_eq!(first.pixels, [RED, RED, RED, RED].concat());
+
+    assert!(frames.frame(2).unwrap().is_none());
+  }
+}
@@ -0,0 +1,126 @@
+use super::WuffsLimits;
+use crate::{
+  boxed::{WuffsBorrowed, WuffsDefault, WuffsHeapWork, WuffsStorage, WuffsWork},
+  status::{IntoResult, WuffsError},
+};
+use wuffs_sys::{wuffs_targa__decoder, wuffs_targa__decoder__initialize, WUFFS_VERSION};
+
+/// TGA decoder, for uncompressed and RLE encoded images.
+///
+/// The work buffer is sized once the image config is decoded.  A heap work buffer grows
+/// to fit; a slice must already be large enough, see
+/// [`workbuf_len`](super::WuffsImageDecoder::workbuf_len).
+pub struct WuffsTargaDecoder<B = WuffsDefault<wuffs_targa__decoder>, W = WuffsHeapWork> {
+  work: W,
+  inner: B,
+  limits: WuffsLimits,
+}
+
+impl WuffsTargaDecoder {
+  #[cfg(feature = "alloc")]
+  pub fn new() -> Result<Self, WuffsError> {
+    Self::with_storage(WuffsDefault::default())
+  }
+}
+
+impl<B: WuffsStorage<wuffs_targa__decoder>> WuffsTargaDecoder<B> {
+  /// Initialize a decoder whose state lives in `inner`, with a heap work buffer.
+  #[cfg(feature = "alloc")]
+  pub fn with_storage(inner: B) -> Result<Self, WuffsError> {
+    Self::with_work(inner, WuffsHeapWork::default())
+  }
+}
+
+impl<'a> WuffsTargaDecoder<WuffsBorrowed<'a, wuffs_targa__decoder>, &'a mut [u8]> {
+  /// Initialize a decoder without allocating: the state lives in `state`, which must
+  /// satisfy [`WuffsBorrowed::new`], and `work` is the work buffer.
+  pub fn from_buffers(
+    state: &'a mut [u8],
+    work: &'a mut [u8],
+  ) -> Result<Self, WuffsError> {
+    Self::with_work(WuffsBorrowed::new(state)?, work)
+  }
+}
+
+impl<B: WuffsStorage<wuffs_targa__decoder>, W: WuffsWork> WuffsTargaDecoder<B, W> {
+  /// Initialize a decoder whose state lives in `inner` and whose work buffer is `work`.
+  pub fn with_work(mut inner: B, work: W) -> Result<Self, WuffsError> {
+    unsafe {
+      wuffs_targa__decoder__initialize(
+        inner.as_mut_ptr(),
+        inner.size() as _,
+        WUFFS_VERSION as _,
+        0,
+      )
+      .into_result()?;
+    }
+
+    Ok(Self {
+      work,
+      inner,
+      limits: WuffsLimits::default(),
+    })
+  }
+
+  /// Borrow `needed` bytes of work buffer, growing it if it can be.
+  fn work_buf(work: &mut W, needed: usize) -> Result<&mut [u8], WuffsError> {
+    work.reserve(needed)
+  }
+}
+
+image_decoder! {
+  WuffsTargaDecoder<W: WuffsWork>, wuffs_targa__decoder {
+    sizeof: sizeof__wuffs_targa__decoder,
+    set_quirk_enabled: wuffs_targa__decoder__set_quirk_enabled,
+    set_quirk: wuffs_targa__decoder__set_quirk,
+    set_report_metadata: wuffs_targa__decoder__set_report_metadata,
+    tell_me_more: wuffs_targa__decoder__tell_me_more,
+    decode_image_config: wuffs_targa__decoder__decode_image_config,
+    decode_frame_config: wuffs_targa__decoder__decode_frame_config,
+    decode_frame: wuffs_targa__decoder__decode_frame,
+    num_animation_loops: wuffs_targa__decoder__num_animation_loops,
+    num_decoded_frame_configs: wuffs_targa__decoder__num_decoded_frame_configs,
+    num_decoded_frames: wuffs_targa__decoder__num_decoded_frames,
+    workbuf_len: wuffs_targa__decoder__workbuf_len,
+    frame_dirty_rect: wuffs_targa__decoder__frame_dirty_rect,
+    restart_frame: wuffs_targa__decoder__restart_frame,
+  }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+pub(crate) mod tests {
+  use super::WuffsTargaDecoder;
+  use crate::{
+    buf::WuffsBuf,
+    pixel::{WuffsPixelBuffer, WuffsPixelConfig, WuffsPixelFormat},
+    std::image::WuffsImageDecoder,
+    swizzler::WuffsPixelBlend,
+  };
+
+  // RED is a 1x1 uncompressed 24-bit TGA holding one red pixel.
+  pub(crate) const RED: [u8; 21] = [
+    0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00,
+    0x01, 0x00, 0x18, 0x20, // 00..11 header, 1x1 24 bpp top to bottom
+    0x00, 0x00, 0xff, // 12..14 red as BGR
+  ];
+
+  #[test]
+  fn test_targa_decode_frame() {
+    let mut src = unsafe { WuffsBuf::from_slice_readonly(&RED) };
+    let mut tga = WuffsTargaDecoder::new().unwrap();
+
+    let image = tga.decode_image_config(&mut src).unwrap();
+    assert_eq!((image.width(), image.height()), (1, 1));
+
+    tga.decode_frame_config(&mut src).unwrap();
+
+    let config = WuffsPixelConfig::new(WuffsPixelFormat::RgbaNonpremul, 1, 1);
+    let mut buf = [0; 4];
+    let mut pixbuf = WuffsPixelBuffer::from_slice(config, &mut buf).unwrap();
+    tga
+      .decode_frame(&mut pixbuf, &mut src, WuffsPixelBlend::Src)
+      .unwrap();
+
+    assert_eq!(pixbuf.pixel(0, 0), Some(&[0xff, 0, 0, 0xff][..]));
+  }
+}
@@ -0,0 +1,182 @@
+use core::mem;
+
+use crate::{
+  pixel::{WuffsPixelBuffer, WuffsPixelFormat, PALETTE_LEN},
+  slice::WuffsSlice,
+  status::{IntoResult, WuffsError},
+};
+use wuffs_sys::{
+  wuffs_base__error__bad_argument, wuffs_base__pixel_format, wuffs_base__pixel_swizzler,
+  wuffs_base__pixel_swizzler__prepare,
+  wuffs_base__pixel_swizzler__swizzle_interleaved_from_slice,
+};
+
+/// How converted pixels are combined with the destination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WuffsPixelBlend {
+  /// Overwrite the destination.
+  Src,
+  /// Composite the source over the destination using the source alpha.
+  SrcOver,
+}
+
+impl WuffsPixelBlend {
+  /// `WUFFS_BASE__PIXEL_BLEND__*` are cast expressions, which bindgen doesn't export.
+  pub fn repr(self) -> u8 {
+    match self {
+      Self::Src => 0,
+      Self::SrcOver => 1,
+    }
+  }
+}
+
+/// Converter between two pixel formats, using SIMD where the C code has it.
+#[derive(Clone)]
+pub struct WuffsPixelSwizzler {
+  inner: wuffs_base__pixel_swizzler,
+  dst_format: WuffsPixelFormat,
+  src_format: WuffsPixelFormat,
+  /// Palette of the source converted to the destination format, read by indexed sources.
+  palette: [u8; PALETTE_LEN],
+}
+
+impl WuffsPixelSwizzler {
+  /// Prepare a conversion from `src_format` to `dst_format`.  `palette` is the BGRA
+  /// palette of an indexed source and is ignored otherwise.
+  pub fn prepare(
+    dst_format: WuffsPixelFormat,
+    src_format: WuffsPixelFormat,
+    palette: &[u8],
+    blend: WuffsPixelBlend,
+  ) -> Result<Self, WuffsError> {
+    let mut swizzler = Self {
+      inner: unsafe { mem::zeroed() },
+      dst_format,
+      src_format,
+      palette: [0; PALETTE_LEN],
+    };
+
+    unsafe {
+      wuffs_base__pixel_swizzler__prepare(
+        &mut swizzler.inner,
+        wuffs_base__pixel_format {
+          repr: dst_format.repr(),
+        },
+        WuffsSlice::from(&mut swizzler.palette[..]).into_inner(),
+        wuffs_base__pixel_format {
+          repr: src_format.repr(),
+        },
+        WuffsSlice::from_readonly(palette),
+        blend.repr() as _,
+      )
+      .into_result()?;
+    }
+
+    Ok(swizzler)
+  }
+
+  pub fn dst_format(&self) -> WuffsPixelFormat {
+    self.dst_format
+  }
+
+  pub fn src_format(&self) -> WuffsPixelFormat {
+    self.src_format
+  }
+
+  /// Palette for an indexed destination, [`PALETTE_LEN`] bytes of BGRA.
+  pub fn palette(&self) -> &[u8] {
+    &self.palette
+  }
+
+  /// Convert the interleaved pixels of `src` into `dst`, stopping when either runs out.
+  /// Returns the number of pixels converted.
+  pub fn swizzle(&self, dst: &mut [u8], src: &[u8]) -> usize {
+    unsafe {
+      wuffs_base__pixel_swizzler__swizzle_interleaved_from_slice(
+        &self.inner,
+        WuffsSlice::from(dst).into_inner(),
+        WuffsSlice::from_readonly(&self.palette),
+        WuffsSlice::from_readonly(src),
+      ) as _
+    }
+  }
+
+  /// Convert `src` into `dst` row by row, over the rows and columns both have.  The
+  /// formats of the buffers must be the ones the swizzler was prepared for, and an
+  /// indexed `src` is looked up in the palette given to [`prepare`](Self::prepare).
+  pub fn swizzle_rows(
+    &self,
+    dst: &mut WuffsPixelBuffer,
+    src: &WuffsPixelBuffer,
+  ) -> Result<(), WuffsError> {
+    if dst.format() != self.dst_format || src.format() != self.src_format {
+      return Err(unsafe {
+        WuffsError::from_ptr(wuffs_base__error__bad_argument.as_ptr())
+      });
+    }
+
+    for (dst_row, src_row) in dst.rows_mut().zip(src.rows()) {
+      self.swizzle(dst_row, src_row);
+    }
+
+    if self.dst_format.is_indexed() {
+      if let Some(palette) = dst.palette_mut() {
+        palette.copy_from_slice(&self.palette);
+      }
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{WuffsPixelBlend, WuffsPixelSwizzler};
+  use crate::pixel::{WuffsPixelBuffer, WuffsPixelConfig, WuffsPixelFormat, PALETTE_LEN};
+
+  #[test]
+  fn test_swizzle_bgra_to_rgba() {
+    let swizzler = WuffsPixelSwizzler::prepare(
+      WuffsPixelFormat::RgbaNonpremul,
+      WuffsPixelFormat::BgraNonpremul,
+      &[],
+      WuffsPixelBlend::Src,
+    )
+    .unwrap();
+
+    let mut dst = [0; 8];
+    let n = swizzler.swizzle(&mut dst, &[1, 2, 3, 4, 5, 6, 7, 8]);
+
+    assert_eq!(n, 2);
+    assert_eq!(dst, [3, 2, 1, 4, 7, 6, 5, 8]);
+  }
+
+  #[test]
+  fn test_swizzle_rows_indexed() {
+    let mut palette = [0; PALETTE_LEN];
+    palette[4..8].copy_from_slice(&[10, 20, 30, 255]);
+
+    let swizzler = WuffsPixelSwizzler::prepare(
+      WuffsPixelFormat::BgraNonpremul,
+      WuffsPixelFormat::IndexedBgraNonpremul,
+      &palette,
+      WuffsPixelBlend::Src,
+    )
+    .unwrap();
+
+    let mut src_buf = [0; PALETTE_LEN + 4];
+    src_buf[PALETTE_LEN + 3] = 1;
+    let src_config = WuffsPixelConfig::new(WuffsPixelFormat::IndexedBgraNonpremul, 2, 2);
+    let src = WuffsPixelBuffer::from_slice(src_config, &mut src_buf).unwrap();
+
+    let mut dst_buf = [0; 2 * 12];
+    let dst_config = WuffsPixelConfig::new(WuffsPixelFormat::BgraNonpremul, 2, 2);
+    let mut dst =
+      WuffsPixelBuffer::from_slice_with_stride(dst_config, &mut dst_buf, 12).unwrap();
+
+    swizzler.swizzle_rows(&mut dst, &src).unwrap();
+
+    assert_eq!(dst.pixel(0, 1), Some(&[0, 0, 0, 0][..]));
+    assert_eq!(dst.pixel(1, 1), Some(&[10, 20, 30, 255][..]));
+  }
+}